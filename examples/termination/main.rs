@@ -6,12 +6,13 @@ use chrono::Utc;
 use clap::{Parser, Subcommand};
 use process_tree::check_tree_size;
 use robotmk::config::{
-    CondaEnvironmentSource, HTTPProxyConfig, RetryStrategy, TlsCertificateValidation,
+    CondaEnvironmentSource, HTTPProxyConfig, RetryPolicyConfig, RetryStrategy, RobotExecutionMode,
+    TerminationLadder, TlsCertificateValidation,
 };
 use robotmk::env::{
     Environment, conda::CondaEnvironment, rcc::RCCEnvironment, system::SystemEnvironment,
 };
-use robotmk::plans::run_attempts_with_rebot;
+use robotmk::plans::{PlanRunSpec, run_attempts_with_rebot};
 use robotmk::results::BuildOutcome;
 use robotmk::rf::robot::Robot;
 use robotmk::session::{CurrentSession, Session};
@@ -68,19 +69,24 @@ fn system_main() -> AnyhowResult<()> {
         ],
         envs_rendered_obfuscated: vec![],
         retry_strategy: RetryStrategy::Complete,
+        execution_mode: RobotExecutionMode::Robot,
+        retry_policy: RetryPolicyConfig::default(),
+        total_budget: None,
+        termination_ladder: TerminationLadder::default(),
     };
     let token = CancellationToken::new();
     let thread_token = token.clone();
     let running = thread::spawn(move || {
-        run_attempts_with_rebot(
-            &robot,
-            "test",
-            &Environment::System(SystemEnvironment {}),
-            &Session::Current(CurrentSession {}),
-            3,
-            &thread_token,
-            &test_dir_path,
-        )
+        run_attempts_with_rebot(&PlanRunSpec {
+            id: "test",
+            robot: &robot,
+            environment: &Environment::System(SystemEnvironment {}),
+            session: &Session::Current(CurrentSession {}),
+            timeout: 3,
+            cancellation_token: &thread_token,
+            output_directory: &test_dir_path,
+            report_activity: &|_| {},
+        })
     });
     while !flag_file.exists() {
         // Wait for all children to be created
@@ -125,6 +131,10 @@ fn rcc_main(rcc_binary_path: Utf8PathBuf) -> AnyhowResult<()> {
         ],
         envs_rendered_obfuscated: vec![],
         retry_strategy: RetryStrategy::Complete,
+        execution_mode: RobotExecutionMode::Robot,
+        retry_policy: RetryPolicyConfig::default(),
+        total_budget: None,
+        termination_ladder: TerminationLadder::default(),
     };
     let rcc_environment = Environment::Rcc(RCCEnvironment {
         binary_path: rcc_binary_path,
@@ -134,6 +144,7 @@ fn rcc_main(rcc_binary_path: Utf8PathBuf) -> AnyhowResult<()> {
         controller: "termination_rcc".into(),
         space: "termination_rcc".into(),
         build_timeout: 1200,
+        termination_ladder: TerminationLadder::default(),
         build_runtime_directory: test_dir_path.to_path_buf(),
         robocorp_home: test_dir_path.join("robocorp_home").to_string(),
     });
@@ -148,15 +159,16 @@ fn rcc_main(rcc_binary_path: Utf8PathBuf) -> AnyhowResult<()> {
     let token = CancellationToken::new();
     let thread_token = token.clone();
     let running = thread::spawn(move || {
-        run_attempts_with_rebot(
-            &robot,
-            "test",
-            &rcc_environment,
-            &session,
-            20,
-            &thread_token,
-            &test_dir_path,
-        )
+        run_attempts_with_rebot(&PlanRunSpec {
+            id: "test",
+            robot: &robot,
+            environment: &rcc_environment,
+            session: &session,
+            timeout: 20,
+            cancellation_token: &thread_token,
+            output_directory: &test_dir_path,
+            report_activity: &|_| {},
+        })
     });
     while !flag_file.exists() {
         // Wait for all children to be created
@@ -201,6 +213,10 @@ fn micromamba_main(micromamba_binary_path: Utf8PathBuf) -> AnyhowResult<()> {
         ],
         envs_rendered_obfuscated: vec![],
         retry_strategy: RetryStrategy::Complete,
+        execution_mode: RobotExecutionMode::Robot,
+        retry_policy: RetryPolicyConfig::default(),
+        total_budget: None,
+        termination_ladder: TerminationLadder::default(),
     };
     let conda_environment = Environment::Conda(CondaEnvironment {
         source: CondaEnvironmentSource::Manifest(
@@ -214,6 +230,7 @@ fn micromamba_main(micromamba_binary_path: Utf8PathBuf) -> AnyhowResult<()> {
         tls_certificate_validation: TlsCertificateValidation::Enabled,
         tls_revokation_enabled: true,
         build_timeout: 1200,
+        termination_ladder: TerminationLadder::default(),
         build_runtime_directory: test_dir_path.clone(),
     });
     let session = Session::Current(CurrentSession {});
@@ -227,15 +244,16 @@ fn micromamba_main(micromamba_binary_path: Utf8PathBuf) -> AnyhowResult<()> {
     let token = CancellationToken::new();
     let thread_token = token.clone();
     let running = thread::spawn(move || {
-        run_attempts_with_rebot(
-            &robot,
-            "test",
-            &conda_environment,
-            &session,
-            20,
-            &thread_token,
-            &test_dir_path,
-        )
+        run_attempts_with_rebot(&PlanRunSpec {
+            id: "test",
+            robot: &robot,
+            environment: &conda_environment,
+            session: &session,
+            timeout: 20,
+            cancellation_token: &thread_token,
+            output_directory: &test_dir_path,
+            report_activity: &|_| {},
+        })
     });
     while !flag_file.exists() {
         // Wait for all children to be created
@@ -1,12 +1,20 @@
 use camino::Utf8PathBuf;
-use clap::Parser;
+use chrono::Utc;
+use clap::{Parser, ValueEnum};
 use robotmk::{
-    config::Config,
+    config::{Config, ExecutionConfig, HooksConfig},
     lock::Locker,
-    results::ConfigSection,
-    results::results_directory,
-    section::{Host, Section, read},
+    plans::total_retry_delay,
+    results::{
+        ConfigSection, PlanExecutionReport, SCHEDULER_HEARTBEAT_INTERVAL_SECS, SchedulerHeartbeat,
+        StaleResult, StaleResults, results_directory,
+    },
+    rf::rebot::REBOT_TIMEOUT,
+    section::{Host, Section, WritePiggybackSection, WriteSection, read},
 };
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::env::{VarError, var};
 use std::fs::read_to_string;
 use std::io;
@@ -17,6 +25,176 @@ struct Args {
     /// Configuration file path.
     #[clap(name = "CONFIG_PATH")]
     pub config_path: Option<Utf8PathBuf>,
+
+    #[command(flatten)]
+    pub filter: SectionFilter,
+
+    /// Output format. JSON output consists of a single document containing all sections.
+    #[clap(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+}
+
+// Each criterion is ignored if left empty. Otherwise, a section has to match one of the given
+// values.
+#[derive(clap::Args, Default)]
+struct SectionFilter {
+    /// Only report sections with this name. Can be specified multiple times.
+    #[clap(long = "section", name = "SECTION")]
+    pub section_names: Vec<String>,
+
+    /// Only report sections concerning this plan. Can be specified multiple times.
+    #[clap(long = "plan", name = "PLAN_ID")]
+    pub plan_ids: Vec<String>,
+
+    /// Only report piggyback sections for this host. Can be specified multiple times.
+    #[clap(long = "host", name = "HOST")]
+    pub hosts: Vec<String>,
+}
+
+impl SectionFilter {
+    fn matches(&self, section: &Section) -> bool {
+        (self.section_names.is_empty() || self.section_names.contains(&section.name))
+            && (self.plan_ids.is_empty()
+                || plan_id(section).is_some_and(|plan_id| self.plan_ids.contains(&plan_id)))
+            && (self.hosts.is_empty()
+                || matches!(&section.host, Host::Piggyback(host) if self.hosts.contains(host)))
+    }
+}
+
+// Sections concerning a single plan carry its id at the top level of their content.
+fn plan_id(section: &Section) -> Option<String> {
+    #[derive(Deserialize)]
+    struct PlanReference {
+        plan_id: String,
+    }
+    serde_json::from_str::<PlanReference>(&section.content)
+        .ok()
+        .map(|reference| reference.plan_id)
+}
+
+// Only the timestamps are needed, such that results written by other scheduler versions can still
+// be checked.
+#[derive(Deserialize)]
+struct HeartbeatTimestamp {
+    timestamp: i64,
+    #[serde(default)]
+    progress: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct PlanReportTimestamp {
+    plan_id: String,
+    timestamp: i64,
+}
+
+// Attempts, retry delays and rebot are bounded by the total budget. Without one, they are bounded
+// by the individual timeouts, the configured delays and the rebot timeout. Hooks come on top.
+fn max_runtime(execution_config: &ExecutionConfig, hooks_config: &HooksConfig) -> u64 {
+    let attempts_and_rebot = match &execution_config.total_budget {
+        Some(total_budget) => total_budget.budget,
+        None => {
+            execution_config.timeout * execution_config.n_attempts_max as u64
+                + total_retry_delay(
+                    &execution_config.retry_policy.delay,
+                    execution_config.n_attempts_max,
+                )
+                .as_secs()
+                + REBOT_TIMEOUT
+        }
+    };
+    attempts_and_rebot
+        + hooks_config
+            .pre_run
+            .iter()
+            .chain(&hooks_config.post_run)
+            .map(|hook_config| hook_config.timeout)
+            .sum::<u64>()
+}
+
+// Within a group, a plan might have to wait for all other plans of the group, be it because they run
+// sequentially or because they occupy the available slots or sessions. If a group run takes longer
+// than the execution interval, the next one starts right after it.
+fn max_result_ages(config: &Config) -> HashMap<String, u64> {
+    config
+        .plan_groups
+        .iter()
+        .flat_map(|group| {
+            let group_runtime: u64 = group
+                .plans
+                .iter()
+                .map(|plan| max_runtime(&plan.execution_config, &plan.hooks))
+                .sum();
+            group.plans.iter().map(move |plan| {
+                (
+                    plan.id.clone(),
+                    group.execution_interval.max(group_runtime) + group_runtime,
+                )
+            })
+        })
+        .collect()
+}
+
+fn stale_results(sections: &[Section], max_ages: &HashMap<String, u64>, now: i64) -> StaleResults {
+    let heartbeat = sections
+        .iter()
+        .filter(|section| section.name == SchedulerHeartbeat::name())
+        .find_map(|section| serde_json::from_str::<HeartbeatTimestamp>(&section.content).ok());
+    let scheduler_heartbeat_age = heartbeat
+        .as_ref()
+        .map(|heartbeat| now - heartbeat.timestamp);
+    let scheduler_heartbeat_stale = scheduler_heartbeat_age
+        .is_none_or(|age| age > 3 * SCHEDULER_HEARTBEAT_INTERVAL_SECS as i64);
+    let scheduler_progress_age = heartbeat
+        .and_then(|heartbeat| heartbeat.progress)
+        .map(|progress| now - progress);
+    let max_progress_age = max_ages.values().max().copied().unwrap_or(0);
+    let scheduler_progress_stale =
+        scheduler_progress_age.is_some_and(|age| age > max_progress_age as i64);
+    let plans = sections
+        .iter()
+        .filter(|section| section.name == <PlanExecutionReport as WritePiggybackSection>::name())
+        .filter_map(|section| serde_json::from_str::<PlanReportTimestamp>(&section.content).ok())
+        .filter_map(|report| {
+            let max_age = *max_ages.get(&report.plan_id)?;
+            let age = now - report.timestamp;
+            (age > max_age as i64).then_some(StaleResult {
+                plan_id: report.plan_id,
+                timestamp: report.timestamp,
+                age,
+                max_age,
+            })
+        })
+        .collect();
+    StaleResults {
+        scheduler_heartbeat_age,
+        scheduler_heartbeat_stale,
+        scheduler_progress_age,
+        scheduler_progress_stale,
+        plans,
+    }
+}
+
+fn stale_results_section(stale_results: &StaleResults) -> Section {
+    Section {
+        host: Host::Source,
+        name: "robotmk_stale_results".into(),
+        content: serde_json::to_string(stale_results)
+            .expect("Unexpected serialization error: StaleResults"),
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum OutputFormat {
+    #[default]
+    Checkmk,
+    Json,
+}
+
+#[derive(Serialize)]
+struct JsonSection<'a> {
+    host: &'a Host,
+    name: &'a str,
+    content: Value,
 }
 
 fn determine_config_path(arg: Option<Utf8PathBuf>) -> Result<Utf8PathBuf, String> {
@@ -35,15 +213,16 @@ fn config_path_from_env() -> Result<Utf8PathBuf, String> {
     Ok(Utf8PathBuf::from(config_dir).join("robotmk.json"))
 }
 
-fn report_config_section(section: &ConfigSection) {
-    let section_serialized =
-        serde_json::to_string(section).expect("Unexpected serialization error: ConfigSection");
-    println!("<<<robotmk_config_v2:sep(0)>>>");
-    println!("{section_serialized}");
+fn config_section(section: &ConfigSection) -> Section {
+    Section {
+        host: Host::Source,
+        name: "robotmk_config_v2".into(),
+        content: serde_json::to_string(section)
+            .expect("Unexpected serialization error: ConfigSection"),
+    }
 }
 
-fn print_sections(sections: &[Section], stdout: &mut impl io::Write) {
-    // TODO: Test this function.
+fn print_sections(sections: &[&Section], stdout: &mut impl io::Write) {
     for section in sections.iter() {
         let mut with_header = format!("<<<{}:sep(0)>>>\n{}\n", section.name, section.content);
         if let Host::Piggyback(host) = &section.host {
@@ -53,36 +232,253 @@ fn print_sections(sections: &[Section], stdout: &mut impl io::Write) {
     }
 }
 
+// The content of sections is embedded as JSON, such that consumers do not have to parse it again.
+fn print_json(sections: &[&Section], stdout: &mut impl io::Write) {
+    let sections: Vec<JsonSection> = sections
+        .iter()
+        .map(|section| JsonSection {
+            host: &section.host,
+            name: &section.name,
+            content: serde_json::from_str(&section.content)
+                .unwrap_or_else(|_| Value::String(section.content.clone())),
+        })
+        .collect();
+    writeln!(
+        stdout,
+        "{}",
+        serde_json::to_string(&sections).expect("Unexpected serialization error: sections")
+    )
+    .unwrap();
+}
+
 fn main() {
     let arguments = Args::parse();
-    let config_path = match determine_config_path(arguments.config_path) {
+    let sections = collect_sections(arguments.config_path);
+    let sections: Vec<&Section> = sections
+        .iter()
+        .filter(|section| arguments.filter.matches(section))
+        .collect();
+    match arguments.output_format {
+        OutputFormat::Checkmk => print_sections(&sections, &mut io::stdout()),
+        OutputFormat::Json => print_json(&sections, &mut io::stdout()),
+    }
+}
+
+fn collect_sections(config_path: Option<Utf8PathBuf>) -> Vec<Section> {
+    let config_path = match determine_config_path(config_path) {
         Ok(p) => p,
-        Err(e) => {
-            report_config_section(&ConfigSection::ReadingError(e));
-            return;
-        }
+        Err(e) => return vec![config_section(&ConfigSection::ReadingError(e))],
     };
     let raw = match read_to_string(&config_path) {
         Ok(raw) => raw,
         Err(e) => {
             let message = format!("Error while reading {config_path}: {e}");
-            report_config_section(&ConfigSection::ReadingError(message));
-            return;
+            return vec![config_section(&ConfigSection::ReadingError(message))];
         }
     };
     let config: Config = match serde_json::from_str(&raw) {
         Ok(config) => config,
         Err(e) => {
             let message = format!("Error while reading {config_path}: {e}");
-            report_config_section(&ConfigSection::ReadingError(message));
-            return;
+            return vec![config_section(&ConfigSection::ReadingError(message))];
         }
     };
-    report_config_section(&ConfigSection::FileContent(raw));
-    let sections = read(
-        results_directory(&config.runtime_directory),
-        &Locker::new(&config_path, None),
-    )
-    .unwrap();
-    print_sections(&sections, &mut io::stdout());
+    let mut sections = vec![config_section(&ConfigSection::FileContent(raw))];
+    sections.extend(
+        read(
+            results_directory(&config.runtime_directory),
+            &Locker::new(&config_path, None),
+        )
+        .unwrap(),
+    );
+    let stale_results = stale_results(&sections, &max_result_ages(&config), Utc::now().timestamp());
+    sections.push(stale_results_section(&stale_results));
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use robotmk::config::{
+        HookConfig, RetryDelay, RetryPolicyConfig, RetryStrategy, TerminationLadder,
+        TotalBudgetConfig,
+    };
+
+    fn sections() -> Vec<Section> {
+        vec![
+            Section {
+                host: Host::Source,
+                name: "robotmk_scheduler_phase".into(),
+                content: "\"Scheduling\"".into(),
+            },
+            Section {
+                host: Host::Piggyback("piggy".into()),
+                name: "robotmk_plan_execution_report".into(),
+                content: r#"{"plan_id":"login","timestamp":1}"#.into(),
+            },
+            Section {
+                host: Host::Source,
+                name: "robotmk_plan_execution_report".into(),
+                content: r#"{"plan_id":"checkout","timestamp":2}"#.into(),
+            },
+        ]
+    }
+
+    fn filtered<'a>(sections: &'a [Section], filter: &SectionFilter) -> Vec<&'a Section> {
+        sections
+            .iter()
+            .filter(|section| filter.matches(section))
+            .collect()
+    }
+
+    #[test]
+    fn filter_by_section_plan_and_host() {
+        let sections = sections();
+        assert_eq!(filtered(&sections, &SectionFilter::default()).len(), 3);
+        assert_eq!(
+            filtered(
+                &sections,
+                &SectionFilter {
+                    section_names: vec!["robotmk_scheduler_phase".into()],
+                    ..Default::default()
+                }
+            )
+            .len(),
+            1
+        );
+        let by_plan = filtered(
+            &sections,
+            &SectionFilter {
+                plan_ids: vec!["checkout".into()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(by_plan.len(), 1);
+        assert_eq!(
+            by_plan[0].content,
+            r#"{"plan_id":"checkout","timestamp":2}"#
+        );
+        let by_host = filtered(
+            &sections,
+            &SectionFilter {
+                hosts: vec!["piggy".into()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(by_host.len(), 1);
+        assert_eq!(by_host[0].host, Host::Piggyback("piggy".into()));
+    }
+
+    #[test]
+    fn max_runtime_covers_delays_rebot_and_hooks() {
+        let mut execution_config = ExecutionConfig {
+            n_attempts_max: 3,
+            retry_strategy: RetryStrategy::Complete,
+            timeout: 60,
+            retry_policy: RetryPolicyConfig {
+                delay: RetryDelay::Constant { seconds: 5 },
+                ..Default::default()
+            },
+            total_budget: None,
+            termination_ladder: TerminationLadder::default(),
+        };
+        let hooks_config = HooksConfig {
+            pre_run: vec![HookConfig {
+                name: "reset".into(),
+                command: vec!["reset_data".into()],
+                timeout: 15,
+            }],
+            post_run: vec![],
+            fail_run_on_pre_run_hook_failure: false,
+        };
+        assert_eq!(
+            max_runtime(&execution_config, &hooks_config),
+            180 + 10 + REBOT_TIMEOUT + 15
+        );
+        execution_config.total_budget = Some(TotalBudgetConfig {
+            budget: 100,
+            rebot_reserve: 10,
+        });
+        assert_eq!(max_runtime(&execution_config, &hooks_config), 115);
+    }
+
+    #[test]
+    fn stale_results_are_flagged() {
+        let mut sections = sections();
+        sections.push(Section {
+            host: Host::Source,
+            name: "robotmk_scheduler_heartbeat".into(),
+            content: r#"{"timestamp":490,"progress":50}"#.into(),
+        });
+        let max_ages = HashMap::from([("login".into(), 500), ("checkout".into(), 1000)]);
+        assert_eq!(
+            stale_results(&sections, &max_ages, 500),
+            StaleResults {
+                scheduler_heartbeat_age: Some(10),
+                scheduler_heartbeat_stale: false,
+                scheduler_progress_age: Some(450),
+                scheduler_progress_stale: false,
+                plans: vec![],
+            }
+        );
+        assert_eq!(
+            stale_results(&sections, &max_ages, 600),
+            StaleResults {
+                scheduler_heartbeat_age: Some(110),
+                scheduler_heartbeat_stale: true,
+                scheduler_progress_age: Some(550),
+                scheduler_progress_stale: false,
+                plans: vec![StaleResult {
+                    plan_id: "login".into(),
+                    timestamp: 1,
+                    age: 599,
+                    max_age: 500,
+                }],
+            }
+        );
+        assert!(stale_results(&sections, &max_ages, 1100).scheduler_progress_stale);
+        assert_eq!(
+            stale_results(&[], &max_ages, 1000),
+            StaleResults {
+                scheduler_heartbeat_age: None,
+                scheduler_heartbeat_stale: true,
+                scheduler_progress_age: None,
+                scheduler_progress_stale: false,
+                plans: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn print_checkmk_format() {
+        let sections = sections();
+        let mut stdout = vec![];
+        print_sections(&sections.iter().take(2).collect::<Vec<_>>(), &mut stdout);
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "<<<robotmk_scheduler_phase:sep(0)>>>
+\"Scheduling\"
+<<<<piggy>>>>
+<<<robotmk_plan_execution_report:sep(0)>>>
+{\"plan_id\":\"login\",\"timestamp\":1}
+<<<<>>>>
+"
+        );
+    }
+
+    #[test]
+    fn print_json_embeds_content() {
+        let sections = sections();
+        let mut stdout = vec![];
+        print_json(&sections.iter().take(2).collect::<Vec<_>>(), &mut stdout);
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            concat!(
+                r#"[{"host":"Source","name":"robotmk_scheduler_phase","content":"Scheduling"},"#,
+                r#"{"host":{"Piggyback":"piggy"},"name":"robotmk_plan_execution_report","#,
+                r#""content":{"plan_id":"login","timestamp":1}}]"#,
+                "\n"
+            )
+        );
+    }
 }
@@ -0,0 +1,72 @@
+use crate::history::configured_plan_ids;
+
+use anyhow::{Result as AnyhowResult, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use robotmk::config::Config;
+use robotmk::results::plans_working_directory;
+use robotmk::run_archive::{ArchivedRun, archived_runs, extract_run, run_archive_path};
+
+const HEADER: &str = "RUN                                   OUTCOME                  SIZE";
+
+pub fn list_archived_runs(config: &Config, plan_id: &str) -> AnyhowResult<()> {
+    let plan_working_directory = plan_working_directory(config, plan_id)?;
+    if !plan_working_directory.exists() {
+        println!("No runs of plan {plan_id} found");
+        return Ok(());
+    }
+    println!("{HEADER}");
+    for archived_run in archived_runs(&plan_working_directory)? {
+        println!("{}", format_archived_run(&archived_run));
+    }
+    Ok(())
+}
+
+pub fn extract_archived_run(
+    config: &Config,
+    plan_id: &str,
+    run: &str,
+    target_directory: &Utf8Path,
+) -> AnyhowResult<()> {
+    let run_directory = plan_working_directory(config, plan_id)?.join(run);
+    extract_run(&run_archive_path(&run_directory), target_directory)?;
+    println!("Extracted run {run} to {}", target_directory.join(run));
+    Ok(())
+}
+
+fn plan_working_directory(config: &Config, plan_id: &str) -> AnyhowResult<Utf8PathBuf> {
+    if !configured_plan_ids(config).iter().any(|id| id == plan_id) {
+        bail!("Plan {plan_id} is not configured");
+    }
+    Ok(plans_working_directory(&config.runtime_directory).join(plan_id))
+}
+
+fn format_archived_run(archived_run: &ArchivedRun) -> String {
+    format!(
+        "{:<37} {:<20} {:>8}",
+        archived_run.name,
+        archived_run
+            .outcome
+            .map(|outcome| format!("{outcome:?}"))
+            .unwrap_or_else(|| "-".into()),
+        archived_run.size,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use robotmk::history::RunOutcome;
+
+    #[test]
+    fn format_archived_run_with_outcome() {
+        assert_eq!(
+            format_archived_run(&ArchivedRun {
+                name: "2024-01-01T00.00.00.000000000+0000".into(),
+                path: "2024-01-01T00.00.00.000000000+0000.tar.gz".into(),
+                size: 4096,
+                outcome: Some(RunOutcome::TestFailures),
+            }),
+            "2024-01-01T00.00.00.000000000+0000    TestFailures             4096"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use camino::Utf8PathBuf;
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(about = "Robotmk control utility.", version)]
+pub struct Args {
+    /// Configuration file path.
+    #[arg(name = "CONFIG_PATH")]
+    pub config_path: Utf8PathBuf,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Show the most recent runs of a plan.
+    History {
+        /// Plan id.
+        #[arg(name = "PLAN")]
+        plan: String,
+
+        /// Number of runs to show.
+        #[arg(long, default_value_t = 50)]
+        last: usize,
+    },
+
+    /// Show the share of runs in which all tests passed.
+    Availability {
+        /// Plan id. If left unspecified, the availability of all plans is shown.
+        #[arg(name = "PLAN")]
+        plan: Option<String>,
+
+        /// Time window in hours.
+        #[arg(long, default_value_t = 24)]
+        hours: i64,
+    },
+
+    /// Run a plan immediately in the running scheduler.
+    Run {
+        /// Plan id.
+        #[arg(name = "PLAN")]
+        plan: String,
+    },
+
+    /// Stop scheduling a plan until it is resumed.
+    Pause {
+        /// Plan id.
+        #[arg(name = "PLAN")]
+        plan: String,
+    },
+
+    /// Resume scheduling a paused plan.
+    Resume {
+        /// Plan id.
+        #[arg(name = "PLAN")]
+        plan: String,
+    },
+
+    /// Show the current status of a plan in the running scheduler.
+    Status {
+        /// Plan id.
+        #[arg(name = "PLAN")]
+        plan: String,
+    },
+
+    /// Show what the scheduler is currently doing for each plan.
+    Live,
+
+    /// List the archived runs of a plan.
+    Archives {
+        /// Plan id.
+        #[arg(name = "PLAN")]
+        plan: String,
+    },
+
+    /// Extract an archived run of a plan.
+    Extract {
+        /// Plan id.
+        #[arg(name = "PLAN")]
+        plan: String,
+
+        /// Run, as listed by the archives command.
+        #[arg(name = "RUN")]
+        run: String,
+
+        /// Directory to extract the run into.
+        #[arg(long, default_value = ".")]
+        target_directory: Utf8PathBuf,
+    },
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result as AnyhowResult, bail};
+use camino::Utf8Path;
+use robotmk::config::Config;
+use robotmk::control::{ControlRequest, ControlResponse, control_socket_path};
+use std::io::{BufRead, BufReader, Write};
+
+pub fn send_request(config: &Config, request: ControlRequest) -> AnyhowResult<()> {
+    match request_scheduler(&control_socket_path(&config.runtime_directory), &request)? {
+        ControlResponse::Accepted => println!("OK"),
+        ControlResponse::Status(status) => {
+            println!("Plan:     {}", status.plan_id);
+            println!("Paused:   {}", status.paused);
+            println!("Running:  {}", status.running);
+            match status.last_run {
+                Some(last_run) => println!(
+                    "Last run: {:?} at {} ({} attempt(s), {}s)",
+                    last_run.outcome, last_run.timestamp, last_run.attempts, last_run.runtime
+                ),
+                None => println!("Last run: none"),
+            }
+        }
+        ControlResponse::Error(error) => bail!(error),
+    }
+    Ok(())
+}
+
+fn request_scheduler(
+    socket_path: &Utf8Path,
+    request: &ControlRequest,
+) -> AnyhowResult<ControlResponse> {
+    let mut connection = connect(socket_path).context(format!(
+        "Failed to connect to {socket_path}, is the scheduler running?"
+    ))?;
+    let mut serialized = serde_json::to_string(request)?;
+    serialized.push('\n');
+    connection
+        .write_all(serialized.as_bytes())
+        .context("Failed to send request")?;
+    let mut response = String::new();
+    BufReader::new(connection)
+        .read_line(&mut response)
+        .context("Failed to receive response")?;
+    serde_json::from_str(&response).context(format!("Invalid response: {response}"))
+}
+
+#[cfg(unix)]
+fn connect(socket_path: &Utf8Path) -> std::io::Result<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(socket_path)
+}
+
+#[cfg(windows)]
+fn connect(socket_path: &Utf8Path) -> std::io::Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(socket_path)
+}
@@ -0,0 +1,96 @@
+use anyhow::{Result as AnyhowResult, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use robotmk::config::Config;
+use robotmk::history::{RunRecord, availability, read, run_history_file};
+use robotmk::results::state_directory;
+
+const HEADER: &str =
+    "TIMESTAMP                  OUTCOME              ATTEMPTS  RUNTIME  PASSED  FAILED  SKIPPED";
+
+pub fn show_history(config: &Config, plan_id: &str, last: usize) -> AnyhowResult<()> {
+    let records = read_plan_history(config, plan_id)?;
+    println!("{HEADER}");
+    for record in &records[records.len().saturating_sub(last)..] {
+        println!("{}", format_record(record));
+    }
+    Ok(())
+}
+
+pub fn show_availability(config: &Config, plan_id: Option<&str>, hours: i64) -> AnyhowResult<()> {
+    let plan_ids = match plan_id {
+        Some(plan_id) => vec![plan_id.to_string()],
+        None => configured_plan_ids(config),
+    };
+    let since = (Utc::now() - TimeDelta::hours(hours)).timestamp();
+    for plan_id in plan_ids {
+        let records = read_plan_history(config, &plan_id)?;
+        match availability(&records, since) {
+            Some(availability) => println!("{plan_id}: {:.1}%", availability * 100.0),
+            None => println!("{plan_id}: no runs in the last {hours}h"),
+        }
+    }
+    Ok(())
+}
+
+fn read_plan_history(config: &Config, plan_id: &str) -> AnyhowResult<Vec<RunRecord>> {
+    if !configured_plan_ids(config).iter().any(|id| id == plan_id) {
+        bail!("Plan {plan_id} is not configured");
+    }
+    read(&run_history_file(
+        &state_directory(&config.runtime_directory),
+        plan_id,
+    ))
+}
+
+pub fn configured_plan_ids(config: &Config) -> Vec<String> {
+    config
+        .plan_groups
+        .iter()
+        .flat_map(|group| group.plans.iter().map(|plan| plan.id.clone()))
+        .collect()
+}
+
+fn format_record(record: &RunRecord) -> String {
+    format!(
+        "{:<26} {:<20} {:>8} {:>7}s {:>7} {:>7} {:>8}",
+        format_timestamp(record.timestamp),
+        format!("{:?}", record.outcome),
+        record.attempts,
+        record.runtime,
+        record.tests.passed,
+        record.tests.failed,
+        record.tests.skipped,
+    )
+}
+
+pub fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|date_time| date_time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use robotmk::history::{RunOutcome, TestCounts};
+
+    #[test]
+    fn format_record_aligns_with_header() {
+        let formatted = format_record(&RunRecord {
+            timestamp: 1700000000,
+            outcome: RunOutcome::TestFailures,
+            attempts: 2,
+            runtime: 73,
+            tests: TestCounts {
+                passed: 10,
+                failed: 1,
+                skipped: 0,
+            },
+        });
+        assert_eq!(
+            formatted,
+            "2023-11-14 22:13:20 UTC    TestFailures                2      73s      10       1        0"
+        );
+        assert_eq!(formatted.len(), HEADER.len());
+    }
+}
@@ -0,0 +1,101 @@
+use crate::history::format_timestamp;
+
+use anyhow::{Context, Result as AnyhowResult};
+use camino::Utf8Path;
+use robotmk::config::Config;
+use robotmk::results::{
+    LiveStatus, PlanActivity, PlanLiveStatus, SchedulerPhase, live_status_file, results_directory,
+    scheduler_phase_file,
+};
+use robotmk::section::Section;
+use serde::de::DeserializeOwned;
+use std::fs::read_to_string;
+
+const HEADER: &str = "PLAN                     ACTIVITY                                      NEXT RUN                 LAST OUTCOME";
+
+pub fn show_live_status(config: &Config) -> AnyhowResult<()> {
+    let results_directory = results_directory(&config.runtime_directory);
+    let phase: SchedulerPhase = read_section(&scheduler_phase_file(&results_directory))?;
+    println!("Scheduler phase: {}", format_phase(&phase));
+    let live_status_file = live_status_file(&results_directory);
+    if !live_status_file.exists() {
+        println!("No plan activity reported yet");
+        return Ok(());
+    }
+    let LiveStatus(statuses) = read_section(&live_status_file)?;
+    println!("{HEADER}");
+    for status in &statuses {
+        println!("{}", format_status(status));
+    }
+    Ok(())
+}
+
+fn read_section<T: DeserializeOwned>(path: &Utf8Path) -> AnyhowResult<T> {
+    let section: Section = serde_json::from_str(
+        &read_to_string(path)
+            .context(format!("Failed to read {path}, is the scheduler running?"))?,
+    )
+    .context(format!("Failed to parse {path}"))?;
+    serde_json::from_str(&section.content).context(format!("Failed to parse content of {path}"))
+}
+
+fn format_phase(phase: &SchedulerPhase) -> String {
+    match phase {
+        SchedulerPhase::GracePeriod(seconds) => format!("grace period ({seconds}s)"),
+        SchedulerPhase::Setup => "setup".into(),
+        SchedulerPhase::EnvironmentBuilding => "environment building".into(),
+        SchedulerPhase::Scheduling => "scheduling".into(),
+    }
+}
+
+fn format_status(status: &PlanLiveStatus) -> String {
+    format!(
+        "{:<24} {:<45} {:<24} {}",
+        status.plan_id,
+        format_activity(&status.activity),
+        status
+            .next_scheduled_run
+            .map(format_timestamp)
+            .unwrap_or_else(|| "-".into()),
+        status
+            .last_outcome
+            .map(|outcome| format!("{outcome:?}"))
+            .unwrap_or_else(|| "-".into()),
+    )
+}
+
+fn format_activity(activity: &PlanActivity) -> String {
+    match activity {
+        PlanActivity::Idle => "idle".into(),
+        PlanActivity::Building { since } => format!("building since {}", format_timestamp(*since)),
+        PlanActivity::Running { since } => format!("running since {}", format_timestamp(*since)),
+        PlanActivity::RunningAttempt { index, since } => {
+            format!("attempt {index} since {}", format_timestamp(*since))
+        }
+        PlanActivity::RunningRebot { since } => {
+            format!("rebot since {}", format_timestamp(*since))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use robotmk::history::RunOutcome;
+
+    #[test]
+    fn format_running_attempt() {
+        assert_eq!(
+            format_status(&PlanLiveStatus {
+                plan_id: "login".into(),
+                activity: PlanActivity::RunningAttempt {
+                    index: 2,
+                    since: 1700000000
+                },
+                next_scheduled_run: Some(1700000300),
+                last_outcome: Some(RunOutcome::AllTestsPassed),
+            }),
+            "login                    attempt 2 since 2023-11-14 22:13:20 UTC       2023-11-14 22:18:20 UTC  AllTestsPassed"
+        );
+    }
+}
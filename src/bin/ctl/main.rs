@@ -0,0 +1,44 @@
+mod archive;
+mod cli;
+mod control;
+mod history;
+mod live;
+
+use anyhow::{Context, Result as AnyhowResult};
+use clap::Parser;
+use cli::{Args, Command};
+use robotmk::config::load;
+use robotmk::control::ControlRequest;
+
+fn main() -> AnyhowResult<()> {
+    let args = Args::parse();
+    let config = load(&args.config_path).context(format!(
+        "Failed to load configuration from {}",
+        args.config_path
+    ))?;
+    match args.command {
+        Command::History { plan, last } => history::show_history(&config, &plan, last),
+        Command::Availability { plan, hours } => {
+            history::show_availability(&config, plan.as_deref(), hours)
+        }
+        Command::Live => live::show_live_status(&config),
+        Command::Archives { plan } => archive::list_archived_runs(&config, &plan),
+        Command::Extract {
+            plan,
+            run,
+            target_directory,
+        } => archive::extract_archived_run(&config, &plan, &run, &target_directory),
+        Command::Run { plan } => {
+            control::send_request(&config, ControlRequest::Run { plan_id: plan })
+        }
+        Command::Pause { plan } => {
+            control::send_request(&config, ControlRequest::Pause { plan_id: plan })
+        }
+        Command::Resume { plan } => {
+            control::send_request(&config, ControlRequest::Resume { plan_id: plan })
+        }
+        Command::Status { plan } => {
+            control::send_request(&config, ControlRequest::Status { plan_id: plan })
+        }
+    }
+}
@@ -1,9 +1,10 @@
-use super::internal_config::{GlobalConfig, Plan};
-use robotmk::env::Environment;
+use super::internal_config::{GlobalConfig, Plan, dry_run_directory};
+use robotmk::config::DryRunValidationConfig;
 use robotmk::lock::Locker;
-use robotmk::results::{BuildOutcome, BuildStates, EnvironmentBuildStage};
+use robotmk::log_context::{self, Phase};
+use robotmk::results::{BuildOutcome, BuildStates, EnvironmentBuildStage, PlanActivity};
+use robotmk::rf::dry_run::{DryRun, DryRunOutcome};
 use robotmk::section::WriteSection;
-use robotmk::session::Session;
 use robotmk::termination::Terminate;
 
 use camino::{Utf8Path, Utf8PathBuf};
@@ -24,9 +25,8 @@ pub fn build_environments(
     let mut completed_plans = Vec::new();
     for plan in plans.into_iter() {
         let outcome = build_environment(
-            &plan.id,
-            &plan.environment,
-            &plan.session,
+            &plan,
+            &global_config.working_directory_environment_building,
             &global_config.cancellation_token,
             &mut build_stage_reporter,
         )?;
@@ -39,26 +39,82 @@ pub fn build_environments(
 }
 
 fn build_environment(
-    id: &str,
-    environment: &Environment,
-    session: &Session,
+    plan: &Plan,
+    working_directory_environment_building: &Utf8Path,
     cancellation_token: &CancellationToken,
     build_stage_reporter: &mut BuildStageReporter,
 ) -> Result<BuildOutcome, Terminate> {
+    let id = plan.id.as_str();
+    let _log_context = log_context::scoped(|context| {
+        context.plan_id = Some(plan.id.clone());
+        context.session = Some(plan.session.id());
+        context.phase = Some(Phase::EnvironmentBuilding);
+    });
     info!("Processing plan {id}");
     let start_time = Utc::now();
     build_stage_reporter.update(
         id,
         EnvironmentBuildStage::InProgress(start_time.timestamp()),
     )?;
-    let outcome = environment.build(id, session, start_time, cancellation_token)?;
+    plan.live_status.set_activity(
+        id,
+        PlanActivity::Building {
+            since: start_time.timestamp(),
+        },
+    );
+    let mut outcome = plan
+        .environment
+        .build(id, &plan.session, start_time, cancellation_token)?;
     if let BuildOutcome::NotNeeded = outcome {
         info!("Nothing to do for plan {id}");
     }
+    if let (
+        BuildOutcome::NotNeeded | BuildOutcome::Success(_),
+        DryRunValidationConfig::Enabled { timeout },
+    ) = (&outcome, &plan.dry_run_validation)
+        && let Some(failure) = validate_with_dry_run(
+            plan,
+            &dry_run_directory(working_directory_environment_building, &plan.id),
+            *timeout,
+            cancellation_token,
+        )?
+    {
+        outcome = failure;
+    }
     build_stage_reporter.update(id, EnvironmentBuildStage::Complete(outcome.clone()))?;
+    plan.live_status.set_activity(id, PlanActivity::Idle);
     Ok(outcome)
 }
 
+fn validate_with_dry_run(
+    plan: &Plan,
+    output_directory: &Utf8Path,
+    timeout: u64,
+    cancellation_token: &CancellationToken,
+) -> Result<Option<BuildOutcome>, Terminate> {
+    info!("Plan {}: validating with dry run", plan.id);
+    Ok(
+        match (DryRun {
+            plan_id: &plan.id,
+            robot: &plan.robot,
+            environment: &plan.environment,
+            session: &plan.session,
+            output_directory,
+            timeout,
+            cancellation_token,
+        })
+        .run()?
+        {
+            DryRunOutcome::Passed => None,
+            DryRunOutcome::Failed(errors) => Some(BuildOutcome::DryRunFailure(errors)),
+            DryRunOutcome::TimedOut { terminated_by } => {
+                Some(BuildOutcome::Timeout { terminated_by })
+            }
+            DryRunOutcome::Error(error) => Some(BuildOutcome::Error(error)),
+        },
+    )
+}
+
 struct BuildStageReporter<'a> {
     build_states: HashMap<String, EnvironmentBuildStage>,
     path: Utf8PathBuf,
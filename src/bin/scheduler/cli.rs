@@ -1,3 +1,5 @@
+use crate::logging::{LogFormat, LogRotation};
+
 use camino::Utf8PathBuf;
 use clap::{ArgAction, Parser};
 use flexi_logger::LogSpecification;
@@ -13,6 +15,15 @@ pub struct Args {
     #[arg(long, name = "LOG_PATH")]
     pub log_path: Option<Utf8PathBuf>,
 
+    /// Log format. JSON records additionally contain the plan id, attempt index, phase and
+    /// session, if applicable.
+    #[arg(long, value_enum, default_value_t)]
+    pub log_format: LogFormat,
+
+    /// Additionally write the log records of each plan run into the working directory of the run.
+    #[arg(long = "per-plan-logs")]
+    pub per_plan_logs: bool,
+
     /// Run flag file. If specified, the program will terminate as soon as this file does not exist.
     #[arg(long, name = "RUN_FLAG")]
     pub run_flag: Option<Utf8PathBuf>,
@@ -22,6 +33,24 @@ pub struct Args {
     #[arg(short, long, action = ArgAction::Count)]
     verbose: u8,
 
+    /// Log specification per module, such as "warn, robotmk::plans=debug". Takes precedence over
+    /// the verbosity switch.
+    #[arg(long, name = "LOG_SPEC", value_parser = |spec: &str| LogSpecification::parse(spec))]
+    log_spec: Option<LogSpecification>,
+
+    /// Rotate the log file once it exceeds this size in bytes. Log files are rotated daily
+    /// regardless.
+    #[arg(long, name = "LOG_ROTATION_SIZE")]
+    log_rotation_size: Option<u64>,
+
+    /// Number of rotated log files to keep.
+    #[arg(long, name = "LOG_KEEP_FILES", default_value_t = 14)]
+    log_keep_files: usize,
+
+    /// Compress rotated log files.
+    #[arg(long = "log-compress")]
+    log_compress: bool,
+
     /// Grace period. If specified, the program will sleep for this amount of seconds after
     /// completing some general setup steps to give the system some time to prepare (eg. session
     /// creation).
@@ -40,10 +69,21 @@ pub struct Args {
 
 impl Args {
     pub fn log_specification(&self) -> LogSpecification {
+        if let Some(log_spec) = &self.log_spec {
+            return log_spec.clone();
+        }
         match self.verbose {
             2.. => LogSpecification::debug(),
             1 => LogSpecification::info(),
             _ => LogSpecification::warn(),
         }
     }
+
+    pub fn log_rotation(&self) -> LogRotation {
+        LogRotation {
+            max_size: self.log_rotation_size,
+            keep_files: self.log_keep_files,
+            compress: self.log_compress,
+        }
+    }
 }
@@ -0,0 +1,142 @@
+use crate::internal_config::GlobalConfig;
+use crate::logging::log_and_return_error;
+
+use chrono::Utc;
+use robotmk::results::{
+    SCHEDULER_HEARTBEAT_INTERVAL_SECS, SchedulerHeartbeat, SchedulerPhase, scheduler_heartbeat_file,
+};
+use robotmk::section::WriteSection;
+use robotmk::termination::Terminate;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread::{sleep, spawn};
+use std::time::{Duration, Instant};
+
+// Created right after loading the configuration, such that the uptime covers virtually the entire
+// lifetime of the scheduler. The phase is kept up to date by the main thread, the progress by the
+// plan group schedulers.
+#[derive(Clone)]
+pub struct Heartbeat {
+    start_time: i64,
+    config_digest: String,
+    phase: Arc<Mutex<SchedulerPhase>>,
+    group_progress: Arc<Mutex<HashMap<usize, i64>>>,
+}
+
+// Handed to the scheduler of a single plan group.
+#[derive(Clone)]
+pub struct GroupProgress {
+    group_index: usize,
+    group_progress: Arc<Mutex<HashMap<usize, i64>>>,
+}
+
+impl GroupProgress {
+    pub fn record(&self) {
+        self.group_progress
+            .lock()
+            .unwrap()
+            .insert(self.group_index, Utc::now().timestamp());
+    }
+}
+
+impl Heartbeat {
+    pub fn new(raw_config: &str) -> Self {
+        Self {
+            start_time: Utc::now().timestamp(),
+            config_digest: format!("{:x}", Sha256::digest(raw_config)),
+            phase: Arc::new(Mutex::new(SchedulerPhase::Setup)),
+            group_progress: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // The progress of the group is recorded right away, such that it counts from the start of
+    // scheduling.
+    pub fn group_progress(&self, group_index: usize) -> GroupProgress {
+        let group_progress = GroupProgress {
+            group_index,
+            group_progress: self.group_progress.clone(),
+        };
+        group_progress.record();
+        group_progress
+    }
+
+    pub fn set_phase(&self, phase: &SchedulerPhase) {
+        *self.phase.lock().unwrap() = phase.clone();
+    }
+
+    fn beat(&self) -> SchedulerHeartbeat {
+        let timestamp = Utc::now().timestamp();
+        SchedulerHeartbeat {
+            timestamp,
+            pid: std::process::id(),
+            start_time: self.start_time,
+            uptime: (timestamp - self.start_time).max(0) as u64,
+            version: env!("CARGO_PKG_VERSION").into(),
+            config_digest: self.config_digest.clone(),
+            phase: self.phase.lock().unwrap().clone(),
+            progress: self.group_progress.lock().unwrap().values().min().copied(),
+        }
+    }
+
+    // Runs on its own thread, such that the heartbeat does not depend on the phase the scheduler
+    // is in. Whether scheduling actually advances is reflected by the progress. Stops once the
+    // scheduler is cancelled.
+    pub fn start(&self, global_config: &GlobalConfig) {
+        let heartbeat = self.clone();
+        let path = scheduler_heartbeat_file(&global_config.results_directory);
+        let locker = global_config.results_directory_locker.clone();
+        let cancellation_token = global_config.cancellation_token.clone();
+        spawn(move || {
+            while !cancellation_token.is_cancelled() {
+                if let Err(Terminate::Unrecoverable(error)) = heartbeat.beat().write(&path, &locker)
+                {
+                    log_and_return_error(error.context("Failed to write scheduler heartbeat"));
+                }
+                let next_beat =
+                    Instant::now() + Duration::from_secs(SCHEDULER_HEARTBEAT_INTERVAL_SECS);
+                while !cancellation_token.is_cancelled() && Instant::now() < next_beat {
+                    sleep(Duration::from_millis(250));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beat_reports_current_phase() {
+        let heartbeat = Heartbeat::new("{}");
+        heartbeat.set_phase(&SchedulerPhase::Scheduling);
+
+        let beat = heartbeat.beat();
+
+        assert_eq!(beat.phase, SchedulerPhase::Scheduling);
+        assert_eq!(beat.progress, None);
+        assert_eq!(beat.pid, std::process::id());
+        assert_eq!(
+            beat.config_digest,
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+    }
+
+    #[test]
+    fn beat_reports_progress_of_group_lagging_behind() {
+        let heartbeat = Heartbeat::new("{}");
+        let first_group = heartbeat.group_progress(0);
+        let second_group = heartbeat.group_progress(1);
+        second_group
+            .group_progress
+            .lock()
+            .unwrap()
+            .insert(second_group.group_index, 100);
+
+        assert_eq!(heartbeat.beat().progress, Some(100));
+        second_group.record();
+        first_group.record();
+        assert!(heartbeat.beat().progress.unwrap() > 100);
+    }
+}
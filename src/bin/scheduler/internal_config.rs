@@ -1,14 +1,23 @@
+use crate::live_status::LiveStatusReporter;
+
 use robotmk::config;
 use robotmk::env::{
     Environment, conda::CondaEnvironment, rcc::RCCEnvironment, system::SystemEnvironment,
 };
+use robotmk::flakiness::flakiness_history_directory;
+use robotmk::history::run_history_file;
+use robotmk::hooks::Hooks;
 use robotmk::lock::Locker;
-use robotmk::results::{plan_results_directory, results_directory};
+use robotmk::results::{
+    SetupFailure, plan_results_directory, plans_working_directory, results_directory,
+    state_directory,
+};
 use robotmk::rf::robot::Robot;
 use robotmk::section::Host;
 use robotmk::session::Session;
 
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::{HashMap, HashSet};
 use tokio_util::sync::CancellationToken;
 
 #[derive(Clone)]
@@ -20,10 +29,14 @@ pub struct GlobalConfig {
     pub working_directory_plans: Utf8PathBuf,
     pub working_directory_environment_building: Utf8PathBuf,
     pub working_directory_rcc_setup_steps: Utf8PathBuf,
+    pub state_directory: Utf8PathBuf,
     pub rcc_config: config::RCCConfig,
     pub conda_config: CondaConfig,
     pub cancellation_token: CancellationToken,
     pub results_directory_locker: Locker,
+    pub cleanup_config: config::CleanupConfig,
+    // Includes the plans which do not survive setup or environment building
+    pub conda_prefixes_in_use: Vec<Utf8PathBuf>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -71,22 +84,66 @@ pub enum Source {
     },
 }
 
+// A managed robot is unpacked into a staging directory first, which then atomically replaces the
+// target directory. The replaced version is kept next to it for rollback purposes.
+pub fn managed_staging_directory(target: &Utf8Path) -> Utf8PathBuf {
+    Utf8PathBuf::from(format!("{target}.staging"))
+}
+
+pub fn managed_previous_directory(target: &Utf8Path) -> Utf8PathBuf {
+    Utf8PathBuf::from(format!("{target}.previous"))
+}
+
+pub fn managed_version_record(target: &Utf8Path) -> Utf8PathBuf {
+    Utf8PathBuf::from(format!("{target}.version.json"))
+}
+
+pub fn managed_previous_version_record(target: &Utf8Path) -> Utf8PathBuf {
+    Utf8PathBuf::from(format!("{target}.previous.version.json"))
+}
+
+pub fn dry_run_directory(
+    working_directory_environment_building: &Utf8Path,
+    plan_id: &str,
+) -> Utf8PathBuf {
+    working_directory_environment_building
+        .join(plan_id)
+        .join("dry_run")
+}
+
 #[derive(Clone)]
 pub struct Plan {
     pub id: String,
     pub source: Source,
     pub working_directory: Utf8PathBuf,
     pub results_file: Utf8PathBuf,
+    pub flakiness_history_file: Utf8PathBuf,
+    pub run_history_file: Utf8PathBuf,
+    pub run_history_max_runs: usize,
     pub timeout: u64,
     pub robot: Robot,
     pub environment: Environment,
     pub session: Session,
     pub working_directory_cleanup_config: config::WorkingDirectoryCleanupConfig,
+    pub failed_run_cleanup_config: Option<config::WorkingDirectoryCleanupConfig>,
     pub cancellation_token: CancellationToken,
     pub host: Host,
     pub results_directory_locker: Locker,
     pub metadata: config::PlanMetadata,
     pub group_affiliation: GroupAffiliation,
+    pub hooks: Hooks,
+    pub dry_run_validation: config::DryRunValidationConfig,
+    pub dependencies: Vec<PlanDependency>,
+    pub live_status: LiveStatusReporter,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanDependency {
+    pub plan_id: String,
+    pub run_history_file: Utf8PathBuf,
+    // Records older than this (execution interval plus timeout of the prerequisite) stem from a
+    // prerequisite which does not run anymore, for example because its environment build failed.
+    pub max_record_age: u64,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -94,34 +151,92 @@ pub struct GroupAffiliation {
     pub group_index: usize,
     pub position_in_group: usize,
     pub execution_interval: u64,
+    pub missed_tick_policy: config::MissedTickPolicy,
+    pub execution_mode: config::GroupExecutionMode,
 }
 
 pub fn from_external_config(
     external_config: config::Config,
     cancellation_token: &CancellationToken,
     results_directory_locker: &Locker,
-) -> (GlobalConfig, Vec<Plan>) {
+) -> (GlobalConfig, Vec<Plan>, Vec<SetupFailure>) {
     let working_directory = external_config.runtime_directory.join("working");
+    let conda_config = CondaConfig {
+        original_micromamba_binary_path: external_config.conda_config.micromamba_binary_path,
+        base_directory: external_config.conda_config.base_directory,
+    };
+    let conda_prefixes_in_use = external_config
+        .plan_groups
+        .iter()
+        .flat_map(|group| group.plans.iter())
+        .filter(|plan_config| {
+            matches!(
+                plan_config.environment_config,
+                config::EnvironmentConfig::Conda(_)
+            )
+        })
+        .map(|plan_config| {
+            conda_config
+                .environments_base_directory()
+                .join(&plan_config.id)
+        })
+        .collect();
     let global_config = GlobalConfig {
         runtime_base_directory: external_config.runtime_directory.clone(),
         working_directory: working_directory.clone(),
         results_directory: results_directory(&external_config.runtime_directory),
         managed_directory: external_config.runtime_directory.join("managed"),
-        working_directory_plans: working_directory.join("plans"),
+        working_directory_plans: plans_working_directory(&external_config.runtime_directory),
         working_directory_environment_building: working_directory.join("environment_building"),
         working_directory_rcc_setup_steps: working_directory.join("rcc_setup"),
+        state_directory: state_directory(&external_config.runtime_directory),
         rcc_config: external_config.rcc_config,
-        conda_config: CondaConfig {
-            original_micromamba_binary_path: external_config.conda_config.micromamba_binary_path,
-            base_directory: external_config.conda_config.base_directory,
-        },
+        conda_config,
         cancellation_token: cancellation_token.clone(),
         results_directory_locker: results_directory_locker.clone(),
+        cleanup_config: external_config.cleanup_config,
+        conda_prefixes_in_use,
     };
 
+    let live_status = LiveStatusReporter::new(
+        &global_config.results_directory,
+        &global_config.results_directory_locker,
+    );
+    let dependencies_by_plan: HashMap<String, Vec<String>> = external_config
+        .plan_groups
+        .iter()
+        .flat_map(|group| group.plans.iter())
+        .map(|plan_config| (plan_config.id.clone(), plan_config.dependencies.clone()))
+        .collect();
+    let max_record_ages: HashMap<String, u64> = external_config
+        .plan_groups
+        .iter()
+        .flat_map(|group| {
+            group.plans.iter().map(|plan_config| {
+                (
+                    plan_config.id.clone(),
+                    group.execution_interval + plan_config.execution_config.timeout,
+                )
+            })
+        })
+        .collect();
     let mut plans = vec![];
+    let mut failures = vec![];
     for (group_index, sequential_group) in external_config.plan_groups.into_iter().enumerate() {
         for (plan_index, plan_config) in sequential_group.plans.into_iter().enumerate() {
+            if let Some(error) = dependency_error(&plan_config.id, &dependencies_by_plan)
+                .or_else(|| total_budget_error(&plan_config.execution_config))
+                .or_else(|| retry_policy_error(&plan_config.execution_config))
+                .or_else(|| execution_mode_error(&plan_config.robot_config.execution_mode))
+                .or_else(|| hooks_error(&plan_config.hooks))
+            {
+                failures.push(SetupFailure {
+                    plan_id: plan_config.id.clone(),
+                    summary: "Invalid plan configuration".into(),
+                    details: error,
+                });
+                continue;
+            }
             let (plan_source_dir, source) = match &plan_config.source {
                 config::Source::Manual { base_dir } => (base_dir.clone(), Source::Manual),
                 config::Source::Managed {
@@ -148,6 +263,10 @@ pub fn from_external_config(
                 working_directory: global_config.working_directory_plans.join(&plan_config.id),
                 results_file: plan_results_directory(&global_config.results_directory)
                     .join(format!("{}.json", plan_config.id)),
+                flakiness_history_file: flakiness_history_directory(&global_config.state_directory)
+                    .join(format!("{}.json", plan_config.id)),
+                run_history_file: run_history_file(&global_config.state_directory, &plan_config.id),
+                run_history_max_runs: external_config.history_config.max_runs_per_plan,
                 timeout: plan_config.execution_config.timeout,
                 robot: Robot::new(
                     config::RobotConfig {
@@ -174,9 +293,13 @@ pub fn from_external_config(
                         environment_variables_rendered_obfuscated: plan_config
                             .robot_config
                             .environment_variables_rendered_obfuscated,
+                        execution_mode: plan_config.robot_config.execution_mode,
                     },
                     plan_config.execution_config.n_attempts_max,
                     plan_config.execution_config.retry_strategy,
+                    plan_config.execution_config.retry_policy,
+                    plan_config.execution_config.total_budget,
+                    plan_config.execution_config.termination_ladder.clone(),
                 ),
                 environment: match plan_config.environment_config {
                     config::EnvironmentConfig::System => Environment::System(SystemEnvironment {}),
@@ -187,6 +310,7 @@ pub fn from_external_config(
                             &plan_config.id,
                             &global_config.rcc_config.binary_path,
                             &rcc_environment_config,
+                            &plan_config.execution_config.termination_ladder,
                             &global_config
                                 .working_directory_environment_building
                                 .join(&plan_config.id),
@@ -222,6 +346,10 @@ pub fn from_external_config(
                                 .tls_certificate_validation,
                             tls_revokation_enabled: conda_environment_config.tls_revokation_enabled,
                             build_timeout: conda_environment_config.build_timeout,
+                            termination_ladder: plan_config
+                                .execution_config
+                                .termination_ladder
+                                .clone(),
                             build_runtime_directory: global_config
                                 .working_directory_environment_building
                                 .join(&plan_config.id),
@@ -230,6 +358,7 @@ pub fn from_external_config(
                 },
                 session,
                 working_directory_cleanup_config: plan_config.working_directory_cleanup_config,
+                failed_run_cleanup_config: plan_config.failed_run_cleanup_config,
                 cancellation_token: cancellation_token.clone(),
                 host: plan_config.host,
                 results_directory_locker: results_directory_locker.clone(),
@@ -238,11 +367,105 @@ pub fn from_external_config(
                     group_index,
                     position_in_group: plan_index,
                     execution_interval: sequential_group.execution_interval,
+                    missed_tick_policy: sequential_group.missed_tick_policy,
+                    execution_mode: sequential_group.execution_mode,
                 },
+                hooks: Hooks::new(
+                    plan_config.hooks,
+                    &plan_config.execution_config.termination_ladder,
+                ),
+                dry_run_validation: plan_config.dry_run_validation,
+                dependencies: plan_config
+                    .dependencies
+                    .iter()
+                    .map(|plan_id| PlanDependency {
+                        plan_id: plan_id.clone(),
+                        run_history_file: run_history_file(&global_config.state_directory, plan_id),
+                        max_record_age: max_record_ages[plan_id],
+                    })
+                    .collect(),
+                live_status: live_status.clone(),
             });
         }
     }
-    (global_config, plans)
+    (global_config, plans, failures)
+}
+
+fn dependency_error(
+    plan_id: &str,
+    dependencies_by_plan: &HashMap<String, Vec<String>>,
+) -> Option<String> {
+    let dependencies = &dependencies_by_plan[plan_id];
+    if let Some(unknown) = dependencies
+        .iter()
+        .find(|dependency| !dependencies_by_plan.contains_key(*dependency))
+    {
+        return Some(format!("Unknown prerequisite plan {unknown}"));
+    }
+    if dependencies.iter().any(|dependency| dependency == plan_id) {
+        return Some("Plan depends on itself".into());
+    }
+    depends_on(plan_id, plan_id, dependencies_by_plan)
+        .then(|| "Plan is part of a dependency cycle".into())
+}
+
+// Without time left for attempts, a run would not execute any.
+fn total_budget_error(execution_config: &config::ExecutionConfig) -> Option<String> {
+    execution_config
+        .total_budget
+        .as_ref()
+        .filter(|total_budget| total_budget.budget <= total_budget.rebot_reserve)
+        .map(|total_budget| {
+            format!(
+                "Total budget ({}s) must exceed the rebot reserve ({}s)",
+                total_budget.budget, total_budget.rebot_reserve
+            )
+        })
+}
+
+fn retry_policy_error(execution_config: &config::ExecutionConfig) -> Option<String> {
+    (execution_config.retry_strategy == config::RetryStrategy::Complete
+        && !execution_config.retry_policy.retry_test_tags.is_empty())
+    .then(|| "Retry test tags require the incremental retry strategy".into())
+}
+
+fn execution_mode_error(execution_mode: &config::RobotExecutionMode) -> Option<String> {
+    match execution_mode {
+        config::RobotExecutionMode::Pabot { processes: 0 } => {
+            Some("Pabot requires at least one process".into())
+        }
+        _ => None,
+    }
+}
+
+// A hook without a command cannot be run. Ignoring it would silently disable a pre-run gate.
+fn hooks_error(hooks_config: &config::HooksConfig) -> Option<String> {
+    hooks_config
+        .pre_run
+        .iter()
+        .chain(&hooks_config.post_run)
+        .find(|hook_config| hook_config.command.is_empty())
+        .map(|hook_config| format!("Hook {} has an empty command", hook_config.name))
+}
+
+fn depends_on(
+    plan_id: &str,
+    target: &str,
+    dependencies_by_plan: &HashMap<String, Vec<String>>,
+) -> bool {
+    let mut visited = HashSet::new();
+    let mut to_visit: Vec<&str> = vec![plan_id];
+    while let Some(current) = to_visit.pop() {
+        for dependency in dependencies_by_plan.get(current).into_iter().flatten() {
+            if dependency == target {
+                return true;
+            }
+            if visited.insert(dependency.as_str()) {
+                to_visit.push(dependency);
+            }
+        }
+    }
+    false
 }
 
 pub fn sort_plans_by_grouping(plans: &mut [Plan]) {
@@ -279,17 +502,25 @@ mod tests {
                 argument_files: vec!["args.txt".into(), "more_args.txt".into()],
                 exit_on_failure: false,
                 environment_variables_rendered_obfuscated: vec![],
+                execution_mode: config::RobotExecutionMode::Robot,
             },
             execution_config: config::ExecutionConfig {
                 n_attempts_max: 1,
                 retry_strategy: config::RetryStrategy::Incremental,
                 timeout: 60,
+                retry_policy: config::RetryPolicyConfig::default(),
+                total_budget: None,
+                termination_ladder: config::TerminationLadder::default(),
             },
             environment_config: config::EnvironmentConfig::System,
             session_config: config::SessionConfig::Current,
             working_directory_cleanup_config: config::WorkingDirectoryCleanupConfig::MaxAgeSecs(
                 1209600,
             ),
+            hooks: config::HooksConfig::default(),
+            dry_run_validation: config::DryRunValidationConfig::Disabled,
+            dependencies: vec![],
+            failed_run_cleanup_config: None,
             host: Host::Source,
             metadata: config::PlanMetadata {
                 application: "sys_app".into(),
@@ -317,11 +548,15 @@ mod tests {
                 argument_files: vec![],
                 exit_on_failure: false,
                 environment_variables_rendered_obfuscated: vec![],
+                execution_mode: config::RobotExecutionMode::Robot,
             },
             execution_config: config::ExecutionConfig {
                 n_attempts_max: 1,
                 retry_strategy: config::RetryStrategy::Complete,
                 timeout: 60,
+                retry_policy: config::RetryPolicyConfig::default(),
+                total_budget: None,
+                termination_ladder: config::TerminationLadder::default(),
             },
             environment_config: config::EnvironmentConfig::Rcc(config::RCCEnvironmentConfig {
                 robot_yaml_path: Utf8PathBuf::from("robot.yaml"),
@@ -338,6 +573,10 @@ mod tests {
             working_directory_cleanup_config: config::WorkingDirectoryCleanupConfig::MaxExecutions(
                 50,
             ),
+            hooks: config::HooksConfig::default(),
+            dry_run_validation: config::DryRunValidationConfig::Disabled,
+            dependencies: vec![],
+            failed_run_cleanup_config: None,
             host: Host::Source,
             metadata: config::PlanMetadata {
                 application: "rcc_app".into(),
@@ -367,11 +606,15 @@ mod tests {
                 argument_files: vec![],
                 exit_on_failure: true,
                 environment_variables_rendered_obfuscated: vec![],
+                execution_mode: config::RobotExecutionMode::Robot,
             },
             execution_config: config::ExecutionConfig {
                 n_attempts_max: 2,
                 retry_strategy: config::RetryStrategy::Incremental,
                 timeout: 60,
+                retry_policy: config::RetryPolicyConfig::default(),
+                total_budget: None,
+                termination_ladder: config::TerminationLadder::default(),
             },
             environment_config: config::EnvironmentConfig::Conda(config::CondaEnvironmentConfig {
                 source: config::CondaEnvironmentSource::Manifest("app1/app1_env.yaml".into()),
@@ -389,6 +632,10 @@ mod tests {
             working_directory_cleanup_config: config::WorkingDirectoryCleanupConfig::MaxExecutions(
                 5,
             ),
+            hooks: config::HooksConfig::default(),
+            dry_run_validation: config::DryRunValidationConfig::Disabled,
+            dependencies: vec![],
+            failed_run_cleanup_config: None,
             host: Host::Source,
             metadata: config::PlanMetadata {
                 application: "app1".into(),
@@ -424,11 +671,15 @@ mod tests {
                         value: "value1".into(),
                     },
                 ],
+                execution_mode: config::RobotExecutionMode::Robot,
             },
             execution_config: config::ExecutionConfig {
                 n_attempts_max: 1,
                 retry_strategy: config::RetryStrategy::Complete,
                 timeout: 60,
+                retry_policy: config::RetryPolicyConfig::default(),
+                total_budget: None,
+                termination_ladder: config::TerminationLadder::default(),
             },
             environment_config: config::EnvironmentConfig::Conda(config::CondaEnvironmentConfig {
                 source: config::CondaEnvironmentSource::Archive("/app2.env.tar.gz".into()),
@@ -453,6 +704,10 @@ mod tests {
             working_directory_cleanup_config: config::WorkingDirectoryCleanupConfig::MaxExecutions(
                 5,
             ),
+            hooks: config::HooksConfig::default(),
+            dry_run_validation: config::DryRunValidationConfig::Disabled,
+            dependencies: vec![],
+            failed_run_cleanup_config: None,
             host: Host::Piggyback("piggy".into()),
             metadata: config::PlanMetadata {
                 application: "app2".into(),
@@ -465,7 +720,7 @@ mod tests {
     #[test]
     fn test_from_external_config() {
         let cancellation_token = CancellationToken::new();
-        let (global_config, plans) = from_external_config(
+        let (global_config, plans, failures) = from_external_config(
             config::Config {
                 runtime_directory: Utf8PathBuf::from("/"),
                 rcc_config: config::RCCConfig {
@@ -482,18 +737,26 @@ mod tests {
                     micromamba_binary_path: "/micromamba".into(),
                     base_directory: Utf8PathBuf::from("/conda_base"),
                 },
+                history_config: config::HistoryConfig::default(),
+                cleanup_config: config::CleanupConfig::default(),
                 plan_groups: vec![
                     config::SequentialPlanGroup {
                         plans: vec![rcc_plan_config()],
                         execution_interval: 300,
+                        missed_tick_policy: config::MissedTickPolicy::Burst,
+                        execution_mode: config::GroupExecutionMode::Sequential,
                     },
                     config::SequentialPlanGroup {
                         plans: vec![system_plan_config()],
                         execution_interval: 300,
+                        missed_tick_policy: config::MissedTickPolicy::Burst,
+                        execution_mode: config::GroupExecutionMode::Sequential,
                     },
                     config::SequentialPlanGroup {
                         plans: vec![conda_manifest_plan_config(), conda_archive_plan_config()],
                         execution_interval: 600,
+                        missed_tick_policy: config::MissedTickPolicy::Burst,
+                        execution_mode: config::GroupExecutionMode::Sequential,
                     },
                 ],
             },
@@ -503,6 +766,7 @@ mod tests {
         assert_eq!(global_config.working_directory, "/working");
         assert_eq!(global_config.results_directory, "/results");
         assert_eq!(global_config.managed_directory, "/managed");
+        assert_eq!(global_config.state_directory, "/state");
         assert_eq!(
             global_config.rcc_config,
             config::RCCConfig {
@@ -521,10 +785,12 @@ mod tests {
                 base_directory: "/conda_base".into(),
             }
         );
+        assert!(failures.is_empty());
         assert_eq!(plans.len(), 4);
         assert_eq!(plans[0].id, "rcc");
         assert_eq!(plans[0].working_directory, "/working/plans/rcc");
         assert_eq!(plans[0].results_file, "/results/plans/rcc.json");
+        assert_eq!(plans[0].flakiness_history_file, "/state/flakiness/rcc.json");
         assert_eq!(plans[0].timeout, 60);
         assert_eq!(
             plans[0].robot,
@@ -537,6 +803,10 @@ mod tests {
                 envs_rendered_obfuscated: vec![],
                 n_attempts_max: 1,
                 retry_strategy: config::RetryStrategy::Complete,
+                execution_mode: config::RobotExecutionMode::Robot,
+                retry_policy: config::RetryPolicyConfig::default(),
+                total_budget: None,
+                termination_ladder: config::TerminationLadder::default(),
             }
         );
         assert_eq!(
@@ -549,6 +819,7 @@ mod tests {
                 controller: "robotmk".into(),
                 space: "rcc".into(),
                 build_timeout: 300,
+                termination_ladder: config::TerminationLadder::default(),
                 build_runtime_directory: Utf8PathBuf::from("/working/environment_building/rcc"),
                 #[cfg(unix)]
                 robocorp_home: Utf8PathBuf::from("/rc_home_base")
@@ -587,6 +858,8 @@ mod tests {
                 group_index: 0,
                 position_in_group: 0,
                 execution_interval: 300,
+                missed_tick_policy: config::MissedTickPolicy::Burst,
+                execution_mode: config::GroupExecutionMode::Sequential,
             }
         );
         assert_eq!(plans[1].id, "system");
@@ -608,6 +881,10 @@ mod tests {
                 envs_rendered_obfuscated: vec![],
                 n_attempts_max: 1,
                 retry_strategy: config::RetryStrategy::Incremental,
+                execution_mode: config::RobotExecutionMode::Robot,
+                retry_policy: config::RetryPolicyConfig::default(),
+                total_budget: None,
+                termination_ladder: config::TerminationLadder::default(),
             }
         );
         assert_eq!(
@@ -633,6 +910,8 @@ mod tests {
                 group_index: 1,
                 position_in_group: 0,
                 execution_interval: 300,
+                missed_tick_policy: config::MissedTickPolicy::Burst,
+                execution_mode: config::GroupExecutionMode::Sequential,
             }
         );
         assert_eq!(plans[2].id, "app1_suite1");
@@ -660,6 +939,10 @@ mod tests {
                 envs_rendered_obfuscated: vec![],
                 n_attempts_max: 2,
                 retry_strategy: config::RetryStrategy::Incremental,
+                execution_mode: config::RobotExecutionMode::Robot,
+                retry_policy: config::RetryPolicyConfig::default(),
+                total_budget: None,
+                termination_ladder: config::TerminationLadder::default(),
             }
         );
         assert_eq!(
@@ -691,6 +974,7 @@ mod tests {
                 tls_certificate_validation: config::TlsCertificateValidation::Enabled,
                 tls_revokation_enabled: false,
                 build_timeout: 300,
+                termination_ladder: config::TerminationLadder::default(),
                 build_runtime_directory: Utf8PathBuf::from(
                     "/working/environment_building/app1_suite1"
                 ),
@@ -715,6 +999,8 @@ mod tests {
                 group_index: 2,
                 position_in_group: 0,
                 execution_interval: 600,
+                missed_tick_policy: config::MissedTickPolicy::Burst,
+                execution_mode: config::GroupExecutionMode::Sequential,
             }
         );
         assert_eq!(plans[3].id, "app2_tests_EN");
@@ -734,6 +1020,10 @@ mod tests {
                 envs_rendered_obfuscated: vec![("env1".into(), "value1".into())],
                 n_attempts_max: 1,
                 retry_strategy: config::RetryStrategy::Complete,
+                execution_mode: config::RobotExecutionMode::Robot,
+                retry_policy: config::RetryPolicyConfig::default(),
+                total_budget: None,
+                termination_ladder: config::TerminationLadder::default(),
             }
         );
         assert_eq!(
@@ -763,6 +1053,7 @@ mod tests {
                 ),
                 tls_revokation_enabled: true,
                 build_timeout: 300,
+                termination_ladder: config::TerminationLadder::default(),
                 build_runtime_directory: Utf8PathBuf::from(
                     "/working/environment_building/app2_tests_EN"
                 ),
@@ -795,7 +1086,133 @@ mod tests {
                 group_index: 2,
                 position_in_group: 1,
                 execution_interval: 600,
+                missed_tick_policy: config::MissedTickPolicy::Burst,
+                execution_mode: config::GroupExecutionMode::Sequential,
             }
         );
     }
+
+    #[test]
+    fn test_total_budget_error() {
+        let execution_config = |budget, rebot_reserve| config::ExecutionConfig {
+            total_budget: Some(config::TotalBudgetConfig {
+                budget,
+                rebot_reserve,
+            }),
+            ..system_plan_config().execution_config
+        };
+        assert_eq!(
+            total_budget_error(&system_plan_config().execution_config),
+            None
+        );
+        assert_eq!(total_budget_error(&execution_config(300, 60)), None);
+        assert_eq!(
+            total_budget_error(&execution_config(60, 60)).unwrap(),
+            "Total budget (60s) must exceed the rebot reserve (60s)"
+        );
+    }
+
+    #[test]
+    fn test_retry_policy_error() {
+        let execution_config = |retry_strategy, retry_test_tags: &[&str]| config::ExecutionConfig {
+            retry_strategy,
+            retry_policy: config::RetryPolicyConfig {
+                retry_test_tags: retry_test_tags.iter().map(|tag| tag.to_string()).collect(),
+                ..Default::default()
+            },
+            ..system_plan_config().execution_config
+        };
+        assert_eq!(
+            retry_policy_error(&execution_config(config::RetryStrategy::Complete, &[])),
+            None
+        );
+        assert_eq!(
+            retry_policy_error(&execution_config(
+                config::RetryStrategy::Incremental,
+                &["flaky"]
+            )),
+            None
+        );
+        assert_eq!(
+            retry_policy_error(&execution_config(
+                config::RetryStrategy::Complete,
+                &["flaky"]
+            ))
+            .unwrap(),
+            "Retry test tags require the incremental retry strategy"
+        );
+    }
+
+    #[test]
+    fn test_execution_mode_error() {
+        assert_eq!(
+            execution_mode_error(&config::RobotExecutionMode::Robot),
+            None
+        );
+        assert_eq!(
+            execution_mode_error(&config::RobotExecutionMode::Pabot { processes: 2 }),
+            None
+        );
+        assert_eq!(
+            execution_mode_error(&config::RobotExecutionMode::Pabot { processes: 0 }).unwrap(),
+            "Pabot requires at least one process"
+        );
+    }
+
+    #[test]
+    fn test_hooks_error() {
+        let hook_config = |name: &str, command: &[&str]| config::HookConfig {
+            name: name.into(),
+            command: command.iter().map(|part| part.to_string()).collect(),
+            timeout: 10,
+        };
+        let hooks_config = |post_run| config::HooksConfig {
+            pre_run: vec![hook_config("reset", &["reset_data", "--all"])],
+            post_run,
+            fail_run_on_pre_run_hook_failure: true,
+        };
+        assert_eq!(hooks_error(&hooks_config(vec![])), None);
+        assert_eq!(
+            hooks_error(&hooks_config(vec![hook_config("notify", &[])])).unwrap(),
+            "Hook notify has an empty command"
+        );
+    }
+
+    #[test]
+    fn test_dependency_error() {
+        let dependencies_by_plan: HashMap<String, Vec<String>> = [
+            ("login", vec![]),
+            ("search", vec!["login"]),
+            ("checkout", vec!["search", "login"]),
+            ("typo", vec!["logni"]),
+            ("selfish", vec!["selfish"]),
+            ("ping", vec!["pong"]),
+            ("pong", vec!["login", "ping"]),
+        ]
+        .into_iter()
+        .map(|(plan_id, dependencies)| {
+            (
+                plan_id.to_string(),
+                dependencies.into_iter().map(String::from).collect(),
+            )
+        })
+        .collect();
+        for valid in ["login", "search", "checkout"] {
+            assert_eq!(dependency_error(valid, &dependencies_by_plan), None);
+        }
+        assert_eq!(
+            dependency_error("typo", &dependencies_by_plan).unwrap(),
+            "Unknown prerequisite plan logni"
+        );
+        assert_eq!(
+            dependency_error("selfish", &dependencies_by_plan).unwrap(),
+            "Plan depends on itself"
+        );
+        for cyclic in ["ping", "pong"] {
+            assert_eq!(
+                dependency_error(cyclic, &dependencies_by_plan).unwrap(),
+                "Plan is part of a dependency cycle"
+            );
+        }
+    }
 }
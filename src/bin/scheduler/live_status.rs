@@ -0,0 +1,37 @@
+use crate::reporter::SectionReporter;
+
+use camino::Utf8Path;
+use robotmk::lock::Locker;
+use robotmk::results::{LiveStatus, PlanActivity, PlanLiveStatus, live_status_file};
+
+// Shared by environment building, the plan group schedulers and the plan runs.
+#[derive(Clone)]
+pub struct LiveStatusReporter(SectionReporter<String, PlanLiveStatus>);
+
+impl LiveStatusReporter {
+    pub fn new(results_directory: &Utf8Path, locker: &Locker) -> Self {
+        Self(SectionReporter::new(
+            live_status_file(results_directory),
+            locker,
+            "live status",
+        ))
+    }
+
+    pub fn set_activity(&self, plan_id: &str, activity: PlanActivity) {
+        self.update(plan_id, |status| status.activity = activity);
+    }
+
+    pub fn update(&self, plan_id: &str, update: impl FnOnce(&mut PlanLiveStatus)) {
+        self.0.update(
+            plan_id.to_string(),
+            || PlanLiveStatus {
+                plan_id: plan_id.to_string(),
+                activity: PlanActivity::Idle,
+                next_scheduled_run: None,
+                last_outcome: None,
+            },
+            update,
+            LiveStatus,
+        );
+    }
+}
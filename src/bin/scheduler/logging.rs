@@ -1,12 +1,26 @@
 use camino::Utf8PathBuf;
+use clap::ValueEnum;
+use flexi_logger::writers::LogWriter;
 use flexi_logger::{
-    Age, Cleanup, Criterion, DeferredNow, FileSpec, FlexiLoggerError, LogSpecification, Logger,
-    LoggerHandle, Naming, Record,
+    Age, Cleanup, Criterion, DeferredNow, Duplicate, FileSpec, FlexiLoggerError, FormatFunction,
+    LogSpecification, Logger, LoggerHandle, Naming, Record,
 };
 use log::error;
+use robotmk::log_context::{self, Phase};
+use serde::Serialize;
 use std::fmt::Debug;
+use std::fs::OpenOptions;
+use std::io::Write;
 
 pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H.%M.%S%.f%z";
+pub const PLAN_LOG_FILE_NAME: &str = "scheduler.log";
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
 
 pub fn format(
     w: &mut dyn std::io::Write,
@@ -25,21 +39,126 @@ pub fn format(
     )
 }
 
+pub struct LogRotation {
+    pub max_size: Option<u64>,
+    pub keep_files: usize,
+    pub compress: bool,
+}
+
+impl LogRotation {
+    fn criterion(&self) -> Criterion {
+        match self.max_size {
+            Some(max_size) => Criterion::AgeOrSize(Age::Day, max_size),
+            None => Criterion::Age(Age::Day),
+        }
+    }
+
+    fn cleanup(&self) -> Cleanup {
+        if self.compress {
+            Cleanup::KeepCompressedFiles(self.keep_files)
+        } else {
+            Cleanup::KeepLogFiles(self.keep_files)
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    timestamp: String,
+    level: &'a str,
+    module: &'a str,
+    file: &'a str,
+    line: u32,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plan_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attempt: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    phase: Option<Phase>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<String>,
+}
+
+pub fn json_format(
+    w: &mut dyn std::io::Write,
+    now: &mut DeferredNow,
+    record: &Record,
+) -> Result<(), std::io::Error> {
+    let context = log_context::current();
+    serde_json::to_writer(
+        w,
+        &JsonRecord {
+            timestamp: now.now_utc_owned().format(TIMESTAMP_FORMAT).to_string(),
+            level: record.level().as_str(),
+            module: record.module_path().unwrap_or("<unnamed>"),
+            file: record.file().unwrap_or("<unnamed>"),
+            line: record.line().unwrap_or(0),
+            message: record.args().to_string(),
+            plan_id: context.plan_id,
+            attempt: context.attempt,
+            phase: context.phase,
+            session: context.session,
+        },
+    )
+    .map_err(std::io::Error::other)
+}
+
+// Additionally writes the records of a plan run into the working directory of the run, such that
+// they are cleaned up along with the run.
+struct PlanLogWriter {
+    format: FormatFunction,
+}
+
+impl LogWriter for PlanLogWriter {
+    fn write(&self, now: &mut DeferredNow, record: &Record) -> std::io::Result<()> {
+        let Some(log_file) = log_context::current().log_file else {
+            return Ok(());
+        };
+        let mut buffer = vec![];
+        (self.format)(&mut buffer, now, record)?;
+        buffer.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_file)?
+            .write_all(&buffer)
+    }
+
+    fn flush(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn format(&mut self, format: FormatFunction) {
+        self.format = format;
+    }
+}
+
 pub fn init(
     specification: LogSpecification,
     path: Option<Utf8PathBuf>,
+    log_format: LogFormat,
+    per_plan_logs: bool,
+    rotation: LogRotation,
 ) -> Result<LoggerHandle, FlexiLoggerError> {
+    let format = match log_format {
+        LogFormat::Text => format,
+        LogFormat::Json => json_format,
+    };
     let logger = Logger::with(specification);
-    match path {
-        Some(path) => logger.log_to_file(FileSpec::try_from(path)?),
-        None => logger.log_to_stderr(),
+    let plan_log_writer = || Box::new(PlanLogWriter { format });
+    match (path, per_plan_logs) {
+        (Some(path), false) => logger.log_to_file(FileSpec::try_from(path)?),
+        (Some(path), true) => {
+            logger.log_to_file_and_writer(FileSpec::try_from(path)?, plan_log_writer())
+        }
+        (None, false) => logger.log_to_stderr(),
+        (None, true) => logger
+            .log_to_writer(plan_log_writer())
+            .duplicate_to_stderr(Duplicate::All),
     }
     .format(format)
-    .rotate(
-        Criterion::Age(Age::Day),
-        Naming::Numbers,
-        Cleanup::KeepLogFiles(14),
-    )
+    .rotate(rotation.criterion(), Naming::Numbers, rotation.cleanup())
     .start()
 }
 
@@ -50,3 +169,34 @@ where
     error!("{error:?}");
     error
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn json_format_includes_context() {
+        let _log_context = log_context::scoped(|context| {
+            context.plan_id = Some("login".into());
+            context.attempt = Some(2);
+            context.phase = Some(Phase::Attempt);
+        });
+        let mut buffer = vec![];
+        json_format(
+            &mut buffer,
+            &mut DeferredNow::new(),
+            &Record::builder()
+                .args(format_args!("running"))
+                .level(Level::Info)
+                .build(),
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(json["message"], "running");
+        assert_eq!(json["plan_id"], "login");
+        assert_eq!(json["attempt"], 2);
+        assert_eq!(json["phase"], "attempt");
+        assert!(json.get("session").is_none());
+    }
+}
@@ -1,7 +1,10 @@
 mod build;
 mod cli;
+mod heartbeat;
 mod internal_config;
+mod live_status;
 mod logging;
+mod reporter;
 mod scheduling;
 mod setup;
 mod termination;
@@ -12,7 +15,7 @@ use log::info;
 use logging::log_and_return_error;
 use robotmk::config::Config;
 use robotmk::lock::Locker;
-use robotmk::results::{SchedulerPhase, SetupFailure, SetupFailures};
+use robotmk::results::{SchedulerPhase, SetupFailure, SetupFailures, scheduler_phase_file};
 use robotmk::section::WriteSection;
 use robotmk::termination::Terminate;
 use std::time::Duration;
@@ -34,20 +37,27 @@ fn main() -> AnyhowResult<()> {
 
 fn run() -> Result<(), Terminate> {
     let args = cli::Args::parse();
-    logging::init(args.log_specification(), args.log_path).context("Logging setup failed.")?;
+    logging::init(
+        args.log_specification(),
+        args.log_path.clone(),
+        args.log_format,
+        args.per_plan_logs,
+        args.log_rotation(),
+    )
+    .context("Logging setup failed.")?;
     info!("Program started and logging set up");
 
-    let external_config = filter_by_plan_id(
-        robotmk::config::load(&args.config_path).context("Configuration loading failed")?,
-        args.plan.as_deref(),
-    )?;
+    let (external_config, raw_config) = robotmk::config::load_with_raw_content(&args.config_path)
+        .context("Configuration loading failed")?;
+    let heartbeat = heartbeat::Heartbeat::new(&raw_config);
+    let external_config = filter_by_plan_id(external_config, args.plan.as_deref())?;
     info!("Configuration loaded");
 
     let cancellation_token = termination::start_termination_control(args.run_flag)
         .context("Failed to set up termination control")?;
     info!("Termination control set up");
 
-    let (global_config, plans) = internal_config::from_external_config(
+    let (global_config, plans, config_failures) = internal_config::from_external_config(
         external_config,
         &cancellation_token,
         &Locker::new(&args.config_path, Some(&cancellation_token)),
@@ -59,20 +69,33 @@ fn run() -> Result<(), Terminate> {
 
     if let Some(grace_period) = args.grace_period {
         info!("Grace period: Sleeping for {grace_period} seconds");
-        write_phase(&SchedulerPhase::GracePeriod(grace_period), &global_config)?;
+        write_phase(
+            &SchedulerPhase::GracePeriod(grace_period),
+            &global_config,
+            &heartbeat,
+        )?;
+    }
+    // Started before the grace period, such that the scheduler does not appear stale meanwhile
+    heartbeat.start(&global_config);
+    if let Some(grace_period) = args.grace_period {
         await_grace_period(grace_period, &cancellation_token);
     }
 
     setup::base_directories::setup(&global_config, &plans)?;
     info!("Base setup completed");
+    #[cfg(target_os = "linux")]
+    robotmk::cgroup::init();
 
     if global_config.cancellation_token.is_cancelled() {
         return Err(Terminate::Cancelled);
     }
 
-    write_phase(&SchedulerPhase::Setup, &global_config)?;
+    write_phase(&SchedulerPhase::Setup, &global_config, &heartbeat)?;
     let (plans, setup_failures) = setup::steps::run::run(&global_config, plans)?;
-    write_setup_failures(setup_failures.into_iter(), &global_config)?;
+    write_setup_failures(
+        config_failures.into_iter().chain(setup_failures),
+        &global_config,
+    )?;
     info!("Setup steps completed");
 
     if global_config.cancellation_token.is_cancelled() {
@@ -80,7 +103,11 @@ fn run() -> Result<(), Terminate> {
     }
 
     info!("Starting environment building");
-    write_phase(&SchedulerPhase::EnvironmentBuilding, &global_config)?;
+    write_phase(
+        &SchedulerPhase::EnvironmentBuilding,
+        &global_config,
+        &heartbeat,
+    )?;
     let plans = build::build_environments(&global_config, plans)?;
     info!("Environment building finished");
 
@@ -89,11 +116,11 @@ fn run() -> Result<(), Terminate> {
     }
 
     info!("Starting plan scheduling");
-    write_phase(&SchedulerPhase::Scheduling, &global_config)?;
+    write_phase(&SchedulerPhase::Scheduling, &global_config, &heartbeat)?;
     let write_plan_results = !args.no_plan_result;
     if args.plan.is_some() {
         if let Some(plan) = plans.first() {
-            let report = scheduling::plans::run_plan(plan)?;
+            let report = scheduling::plans::run_plan(plan, write_plan_results)?;
             if write_plan_results {
                 scheduling::plans::write_plan_result(plan, &report)?;
             } else {
@@ -110,16 +137,23 @@ fn run() -> Result<(), Terminate> {
         }
     }
 
-    scheduling::scheduler::run_plans_and_cleanup(&global_config, &plans, write_plan_results);
+    scheduling::scheduler::run_plans_and_cleanup(
+        &global_config,
+        &plans,
+        write_plan_results,
+        &heartbeat,
+    );
     Err(Terminate::Cancelled)
 }
 
 fn write_phase(
     phase: &SchedulerPhase,
     global_config: &internal_config::GlobalConfig,
+    heartbeat: &heartbeat::Heartbeat,
 ) -> Result<(), Terminate> {
+    heartbeat.set_phase(phase);
     phase.write(
-        global_config.results_directory.join("scheduler_phase.json"),
+        scheduler_phase_file(&global_config.results_directory),
         &global_config.results_directory_locker,
     )
 }
@@ -166,8 +200,10 @@ mod tests {
     use super::*;
     use camino::Utf8PathBuf;
     use robotmk::config::{
-        CondaConfig, EnvironmentConfig, ExecutionConfig, PlanConfig, PlanMetadata, RCCConfig,
-        RCCProfileConfig, RetryStrategy, RobotConfig, SequentialPlanGroup, SessionConfig, Source,
+        CleanupConfig, CondaConfig, DryRunValidationConfig, EnvironmentConfig, ExecutionConfig,
+        GroupExecutionMode, HistoryConfig, HooksConfig, MissedTickPolicy, PlanConfig, PlanMetadata,
+        RCCConfig, RCCProfileConfig, RetryPolicyConfig, RetryStrategy, RobotConfig,
+        RobotExecutionMode, SequentialPlanGroup, SessionConfig, Source, TerminationLadder,
         WorkingDirectoryCleanupConfig,
     };
     use robotmk::section::Host;
@@ -184,6 +220,8 @@ mod tests {
                 micromamba_binary_path: Utf8PathBuf::from("/test/micromamba"),
                 base_directory: Utf8PathBuf::from("/test/conda"),
             },
+            history_config: HistoryConfig::default(),
+            cleanup_config: CleanupConfig::default(),
             plan_groups: vec![SequentialPlanGroup {
                 plans: vec![
                     PlanConfig {
@@ -203,16 +241,24 @@ mod tests {
                             argument_files: vec![],
                             exit_on_failure: false,
                             environment_variables_rendered_obfuscated: vec![],
+                            execution_mode: RobotExecutionMode::Robot,
                         },
                         execution_config: ExecutionConfig {
                             n_attempts_max: 1,
                             retry_strategy: RetryStrategy::Complete,
                             timeout: 60,
+                            retry_policy: RetryPolicyConfig::default(),
+                            total_budget: None,
+                            termination_ladder: TerminationLadder::default(),
                         },
                         environment_config: EnvironmentConfig::System,
                         session_config: SessionConfig::Current,
                         working_directory_cleanup_config:
                             WorkingDirectoryCleanupConfig::MaxExecutions(5),
+                        hooks: HooksConfig::default(),
+                        dry_run_validation: DryRunValidationConfig::Disabled,
+                        dependencies: vec![],
+                        failed_run_cleanup_config: None,
                         host: Host::Source,
                         metadata: PlanMetadata {
                             application: "test_app".to_string(),
@@ -237,16 +283,24 @@ mod tests {
                             argument_files: vec![],
                             exit_on_failure: false,
                             environment_variables_rendered_obfuscated: vec![],
+                            execution_mode: RobotExecutionMode::Robot,
                         },
                         execution_config: ExecutionConfig {
                             n_attempts_max: 1,
                             retry_strategy: RetryStrategy::Complete,
                             timeout: 60,
+                            retry_policy: RetryPolicyConfig::default(),
+                            total_budget: None,
+                            termination_ladder: TerminationLadder::default(),
                         },
                         environment_config: EnvironmentConfig::System,
                         session_config: SessionConfig::Current,
                         working_directory_cleanup_config:
                             WorkingDirectoryCleanupConfig::MaxExecutions(5),
+                        hooks: HooksConfig::default(),
+                        dry_run_validation: DryRunValidationConfig::Disabled,
+                        dependencies: vec![],
+                        failed_run_cleanup_config: None,
                         host: Host::Source,
                         metadata: PlanMetadata {
                             application: "test_app".to_string(),
@@ -256,6 +310,8 @@ mod tests {
                     },
                 ],
                 execution_interval: 300,
+                missed_tick_policy: MissedTickPolicy::Burst,
+                execution_mode: GroupExecutionMode::Sequential,
             }],
         }
     }
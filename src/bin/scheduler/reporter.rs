@@ -0,0 +1,47 @@
+use crate::logging::log_and_return_error;
+
+use camino::Utf8PathBuf;
+use robotmk::lock::Locker;
+use robotmk::section::WriteSection;
+use robotmk::termination::Terminate;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+// Keeps one entry per key and rewrites the whole section on every update. The section is written
+// while holding the lock, such that concurrent updates cannot overwrite newer states with older
+// ones. Failing to write is not fatal, the next update will try again.
+#[derive(Clone)]
+pub struct SectionReporter<K, V> {
+    entries: Arc<Mutex<BTreeMap<K, V>>>,
+    path: Utf8PathBuf,
+    locker: Locker,
+    description: &'static str,
+}
+
+impl<K: Ord, V: Clone> SectionReporter<K, V> {
+    pub fn new(path: Utf8PathBuf, locker: &Locker, description: &'static str) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(BTreeMap::new())),
+            path,
+            locker: locker.clone(),
+            description,
+        }
+    }
+
+    pub fn update<S: WriteSection + Serialize>(
+        &self,
+        key: K,
+        initial: impl FnOnce() -> V,
+        update: impl FnOnce(&mut V),
+        section: impl FnOnce(Vec<V>) -> S,
+    ) {
+        let mut entries = self.entries.lock().unwrap();
+        update(entries.entry(key).or_insert_with(initial));
+        if let Err(Terminate::Unrecoverable(error)) =
+            section(entries.values().cloned().collect()).write(&self.path, &self.locker)
+        {
+            log_and_return_error(error.context(format!("Failed to write {}", self.description)));
+        }
+    }
+}
@@ -1,36 +1,143 @@
-use crate::internal_config::Plan;
+use crate::internal_config::{GlobalConfig, Plan};
 use crate::log_and_return_error;
-use robotmk::config::WorkingDirectoryCleanupConfig;
+use robotmk::config::{CleanupConfig, WorkingDirectoryCleanupConfig};
+use robotmk::history::{RunOutcome, read_run_outcome, run_outcome_file};
+use robotmk::run_archive::{archive_run, is_run_archive, read_archived_run_outcome};
 
 use anyhow::{Context, Result as AnyhowResult};
-use camino::{Utf8DirEntry, Utf8Path};
+use camino::{Utf8DirEntry, Utf8Path, Utf8PathBuf};
 use log::{debug, info};
-use std::cmp::min;
+use std::cmp::{Reverse, min};
 use std::fs::{remove_dir_all, remove_file};
 use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
 
-pub fn cleanup_working_directories<'a>(plans: impl Iterator<Item = &'a Plan>) {
+pub fn cleanup(global_config: &GlobalConfig, plans: &[Plan]) {
+    cleanup_working_directories(plans, &global_config.cleanup_config);
+    for directory in [
+        &global_config.working_directory_environment_building,
+        &global_config.working_directory_rcc_setup_steps,
+    ] {
+        info!("Cleaning up build logs in {directory}");
+        remove_files_older_than(
+            directory,
+            global_config.cleanup_config.build_logs_max_age_secs,
+            unix_now(),
+        );
+    }
+    let _ = cleanup_conda_prefixes(
+        &global_config.conda_config.environments_base_directory(),
+        &global_config.conda_prefixes_in_use,
+    )
+    .context("Error while cleaning up Conda environments")
+    .map_err(log_and_return_error);
+}
+
+fn cleanup_working_directories(plans: &[Plan], cleanup_config: &CleanupConfig) {
+    let mut remaining_runs = vec![];
     for plan in plans {
         info!(
             "Cleaning up working directory {} of plan {}",
             plan.working_directory, plan.id
         );
-        let _ = cleanup_working_directory(
-            &plan.working_directory,
-            &plan.working_directory_cleanup_config,
-        )
-        .context(format!(
+        match cleanup_working_directory(plan, cleanup_config).context(format!(
             "Error while cleaning up working directory of plan {}",
             plan.id
-        ))
-        .map_err(log_and_return_error);
+        )) {
+            Ok(runs) => remaining_runs.push(runs),
+            Err(error) => {
+                log_and_return_error(error);
+            }
+        }
+    }
+    if let Some(quota) = cleanup_config.working_directory_quota {
+        info!("Enforcing working directory quota of {quota} bytes");
+        enforce_quota(remaining_runs, quota);
     }
 }
 
-fn cleanup_working_directory(
-    directory: &Utf8Path,
-    cleanup_config: &WorkingDirectoryCleanupConfig,
+fn remove_files_older_than(directory: &Utf8Path, max_age_secs: u64, now: u64) {
+    for entry in WalkDir::new(directory)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+    {
+        let Some(mtime) = entry
+            .metadata()
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+        else {
+            continue;
+        };
+        if now.saturating_sub(mtime.as_secs()) > max_age_secs {
+            debug!("Removing {}", entry.path().display());
+            let _ = remove_file(entry.path())
+                .context(format!("Failed to remove {}", entry.path().display()))
+                .map_err(log_and_return_error);
+        }
+    }
+}
+
+// Prefixes of plans which were removed from the configuration (or do not use Conda anymore) are
+// not reused by anybody.
+fn cleanup_conda_prefixes(
+    environments_directory: &Utf8Path,
+    prefixes_in_use: &[Utf8PathBuf],
 ) -> AnyhowResult<()> {
+    if !environments_directory.exists() {
+        return Ok(());
+    }
+    for dir_entry in environments_directory.read_dir_utf8()? {
+        let dir_entry = dir_entry?;
+        if !prefixes_in_use
+            .iter()
+            .any(|prefix| prefix == dir_entry.path())
+        {
+            info!("Removing unused Conda environment {}", dir_entry.path());
+            let _ = remove_dir_entry(&dir_entry).map_err(log_and_return_error);
+        }
+    }
+    Ok(())
+}
+
+// An entry of a plan working directory. Sizes are only measured if a byte limit applies, since
+// this requires walking the entire directory tree.
+struct Run {
+    dir_entry: Utf8DirEntry,
+    mtime: u64,
+    size: u64,
+    failed: bool,
+}
+
+fn limits_bytes(cleanup_config: &WorkingDirectoryCleanupConfig) -> bool {
+    matches!(
+        cleanup_config,
+        WorkingDirectoryCleanupConfig::MaxTotalBytes(_)
+            | WorkingDirectoryCleanupConfig::Combined {
+                max_total_bytes: Some(_),
+                ..
+            }
+    )
+}
+
+// Failed runs are subject to their own policy, if configured. Runs without outcome marker, such as
+// the one in progress, are treated like successful ones.
+fn cleanup_working_directory(
+    plan: &Plan,
+    cleanup_config: &CleanupConfig,
+) -> AnyhowResult<Vec<Run>> {
+    let directory = &plan.working_directory;
+    let now = unix_now();
+    if let Some(archive_after_secs) = cleanup_config.archive_after_secs {
+        archive_old_runs(directory, archive_after_secs, now)?;
+    }
+    let measure_sizes = cleanup_config.working_directory_quota.is_some()
+        || limits_bytes(&plan.working_directory_cleanup_config)
+        || plan
+            .failed_run_cleanup_config
+            .as_ref()
+            .is_some_and(limits_bytes);
     let dir_entries = directory
         .read_dir_utf8()?
         .filter_map(|dir_entry_result| {
@@ -40,70 +147,145 @@ fn cleanup_working_directory(
                 .ok()
         })
         .collect::<Vec<_>>();
-    for dir_entry in match cleanup_config {
+    let runs = sort_dir_entries_by_mtime(dir_entries, measure_sizes);
+    let Some(failed_run_cleanup_config) = &plan.failed_run_cleanup_config else {
+        return Ok(apply_cleanup_config(
+            runs,
+            &plan.working_directory_cleanup_config,
+            now,
+        ));
+    };
+    let (failed_runs, other_runs): (Vec<Run>, Vec<Run>) =
+        runs.into_iter().partition(|run| run.failed);
+    let mut runs_to_keep =
+        apply_cleanup_config(other_runs, &plan.working_directory_cleanup_config, now);
+    runs_to_keep.extend(apply_cleanup_config(
+        failed_runs,
+        failed_run_cleanup_config,
+        now,
+    ));
+    runs_to_keep.sort_by_key(|run| Reverse(run.mtime));
+    Ok(runs_to_keep)
+}
+
+fn archive_old_runs(directory: &Utf8Path, archive_after_secs: u64, now: u64) -> AnyhowResult<()> {
+    let runs = sort_dir_entries_by_mtime(directory.read_dir_utf8()?.flatten().collect(), false);
+    for run in runs_to_archive(runs, archive_after_secs, now) {
+        debug!("Archiving {}", run.dir_entry.path());
+        let _ = archive_run(run.dir_entry.path())
+            .context(format!("Failed to archive {}", run.dir_entry.path()))
+            .map_err(log_and_return_error);
+    }
+    Ok(())
+}
+
+// The modification time of a run directory only changes when entries are added directly to it, so
+// a long-running attempt can make the run in progress look old. Hence, the newest run and runs
+// without outcome marker are never archived.
+fn runs_to_archive(runs: Vec<Run>, archive_after_secs: u64, now: u64) -> impl Iterator<Item = Run> {
+    runs.into_iter().skip(1).filter(move |run| {
+        run.dir_entry
+            .file_type()
+            .is_ok_and(|file_type| file_type.is_dir())
+            && now.saturating_sub(run.mtime) > archive_after_secs
+            && run_outcome_file(run.dir_entry.path()).exists()
+    })
+}
+
+// Removes the runs exceeding the limits and returns the remaining ones
+fn apply_cleanup_config(
+    runs: Vec<Run>,
+    cleanup_config: &WorkingDirectoryCleanupConfig,
+    now: u64,
+) -> Vec<Run> {
+    let number_of_runs_to_keep = number_of_runs_to_keep(&runs, cleanup_config, now);
+    let (runs_to_keep, runs_to_remove) = split_vec(runs, number_of_runs_to_keep);
+    for run in runs_to_remove {
+        let _ = remove_dir_entry(&run.dir_entry).map_err(log_and_return_error);
+    }
+    runs_to_keep
+}
+
+// Since the runs are sorted from newest to oldest, every policy amounts to keeping a prefix.
+fn number_of_runs_to_keep(
+    runs: &[Run],
+    cleanup_config: &WorkingDirectoryCleanupConfig,
+    now: u64,
+) -> usize {
+    match cleanup_config {
         WorkingDirectoryCleanupConfig::MaxAgeSecs(max_age_secs) => {
-            dir_entries_to_remove_max_age(dir_entries, *max_age_secs)
+            number_of_runs_within_max_age(runs, *max_age_secs, now)
         }
-        WorkingDirectoryCleanupConfig::MaxExecutions(max_executions) => {
-            dir_entries_to_remove_max_executions(dir_entries, *max_executions)
+        WorkingDirectoryCleanupConfig::MaxExecutions(max_executions) => *max_executions,
+        WorkingDirectoryCleanupConfig::MaxTotalBytes(max_total_bytes) => {
+            number_of_runs_within_bytes(runs.iter(), *max_total_bytes)
         }
-    } {
-        let _ = remove_dir_entry(&dir_entry).map_err(log_and_return_error);
+        WorkingDirectoryCleanupConfig::Combined {
+            max_age_secs,
+            max_executions,
+            max_total_bytes,
+        } => [
+            max_age_secs.map(|max_age_secs| number_of_runs_within_max_age(runs, max_age_secs, now)),
+            *max_executions,
+            max_total_bytes
+                .map(|max_total_bytes| number_of_runs_within_bytes(runs.iter(), max_total_bytes)),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(runs.len()),
     }
-    Ok(())
 }
 
-fn dir_entries_to_remove_max_age(
-    dir_entries: Vec<Utf8DirEntry>,
-    max_age_secs: u64,
-) -> Vec<Utf8DirEntry> {
-    let now = SystemTime::now();
-    dir_entries
-        .into_iter()
-        .filter(|dir_entry| {
-            is_dir_entry_too_old(dir_entry, max_age_secs, &now)
-                .context("Failure while checking if directory entry should be removed")
-                .map_err(log_and_return_error)
-                .unwrap_or(false)
+fn number_of_runs_within_max_age(runs: &[Run], max_age_secs: u64, now: u64) -> usize {
+    runs.iter()
+        .take_while(|run| now.saturating_sub(run.mtime) <= max_age_secs)
+        .count()
+}
+
+// The newest run is always kept, it might still be in progress.
+fn number_of_runs_within_bytes<'a>(runs: impl Iterator<Item = &'a Run>, max_bytes: u64) -> usize {
+    let mut total_bytes = 0;
+    runs.enumerate()
+        .take_while(|(index, run)| {
+            total_bytes += run.size;
+            *index == 0 || total_bytes <= max_bytes
         })
-        .collect()
+        .count()
 }
 
-fn is_dir_entry_too_old(
-    dir_entry: &Utf8DirEntry,
-    max_age_secs: u64,
-    now: &SystemTime,
-) -> AnyhowResult<bool> {
-    match now.duration_since(
-        dir_entry
-            .metadata()
-            .context(format!(
-                "Failed to retrieve metadata of {}",
-                dir_entry.path()
-            ))?
-            .modified()
-            .context(format!(
-                "Failed to retrieve modification time of {}",
-                dir_entry.path()
-            ))?,
-    ) {
-        Ok(duration) => Ok(duration.as_secs() > max_age_secs),
-        // now is earlier than modification time --> directory was modified in the meantime, so keep
-        Err(_) => Ok(false),
-    }
-}
-
-fn dir_entries_to_remove_max_executions(
-    dir_entries: Vec<Utf8DirEntry>,
-    max_executions: usize,
-) -> Vec<Utf8DirEntry> {
-    let mut dir_entries_sorted_by_mtime = sort_dir_entries_by_mtime(dir_entries);
-    dir_entries_sorted_by_mtime.reverse();
-    split_vec(dir_entries_sorted_by_mtime, max_executions).1
-}
-
-fn sort_dir_entries_by_mtime(dir_entries: Vec<Utf8DirEntry>) -> Vec<Utf8DirEntry> {
-    let mut dir_entries_with_mtime: Vec<(Utf8DirEntry, u64)> = dir_entries
+// Removes the oldest runs across all plans until the quota is met, except for the newest run of
+// each plan.
+fn enforce_quota(runs_per_plan: Vec<Vec<Run>>, quota: u64) {
+    let mut total_bytes: u64 = runs_per_plan.iter().flatten().map(|run| run.size).sum();
+    let mut removable_runs: Vec<Run> = runs_per_plan
+        .into_iter()
+        .flat_map(|runs| runs.into_iter().skip(1))
+        .collect();
+    removable_runs.sort_by_key(|run| run.mtime);
+    for run in removable_runs {
+        if total_bytes <= quota {
+            break;
+        }
+        if remove_dir_entry(&run.dir_entry)
+            .map_err(log_and_return_error)
+            .is_ok()
+        {
+            total_bytes = total_bytes.saturating_sub(run.size);
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+// Sorted from newest to oldest
+fn sort_dir_entries_by_mtime(dir_entries: Vec<Utf8DirEntry>, measure_sizes: bool) -> Vec<Run> {
+    let mut runs: Vec<Run> = dir_entries
         .into_iter()
         .filter_map(|dir_entry| {
             dir_entry
@@ -136,14 +318,43 @@ fn sort_dir_entries_by_mtime(dir_entries: Vec<Utf8DirEntry>) -> Vec<Utf8DirEntry
                 ))
                 .map_err(log_and_return_error)
                 .ok()
-                .map(|duration| (dir_entry, duration.as_secs()))
+                .map(|duration| Run {
+                    size: if measure_sizes {
+                        disk_usage(dir_entry.path())
+                    } else {
+                        0
+                    },
+                    failed: is_failed_run(dir_entry.path()),
+                    dir_entry,
+                    mtime: duration.as_secs(),
+                })
         })
         .collect();
-    dir_entries_with_mtime.sort_by_key(|(_dir_entry, mtime)| *mtime);
-    dir_entries_with_mtime
+    runs.sort_by_key(|run| Reverse(run.mtime));
+    runs
+}
+
+fn is_failed_run(path: &Utf8Path) -> bool {
+    match if is_run_archive(path) {
+        read_archived_run_outcome(path)
+    } else {
+        read_run_outcome(path)
+    } {
+        Ok(outcome) => outcome.is_some_and(|outcome| outcome != RunOutcome::AllTestsPassed),
+        Err(error) => {
+            log_and_return_error(error);
+            false
+        }
+    }
+}
+
+fn disk_usage(path: &Utf8Path) -> u64 {
+    WalkDir::new(path)
         .into_iter()
-        .map(|(dir_entry, _mtime)| dir_entry)
-        .collect()
+        .filter_map(|entry| entry.ok()?.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
 }
 
 fn split_vec<T>(mut vector: Vec<T>, at: usize) -> (Vec<T>, Vec<T>) {
@@ -171,6 +382,95 @@ fn remove_dir_entry(dir_entry: &Utf8DirEntry) -> AnyhowResult<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use robotmk::history::write_run_outcome;
+
+    fn runs(directory: &Utf8Path, runs: &[(u64, u64)]) -> Vec<Run> {
+        for (index, _) in runs.iter().enumerate() {
+            std::fs::create_dir(directory.join(index.to_string())).unwrap();
+        }
+        let mut dir_entries: Vec<Utf8DirEntry> = directory
+            .read_dir_utf8()
+            .unwrap()
+            .map(|dir_entry| dir_entry.unwrap())
+            .collect();
+        dir_entries.sort_by_key(|dir_entry| dir_entry.file_name().parse::<usize>().unwrap());
+        dir_entries
+            .into_iter()
+            .zip(runs)
+            .map(|(dir_entry, (mtime, size))| Run {
+                dir_entry,
+                mtime: *mtime,
+                size: *size,
+                failed: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn combined_policy_keeps_strictest_prefix() {
+        let directory = tempfile::tempdir().unwrap();
+        let runs = runs(
+            Utf8Path::from_path(directory.path()).unwrap(),
+            &[(1000, 100), (900, 100), (800, 100), (700, 100)],
+        );
+        let config = |max_age_secs, max_executions, max_total_bytes| {
+            WorkingDirectoryCleanupConfig::Combined {
+                max_age_secs,
+                max_executions,
+                max_total_bytes,
+            }
+        };
+        assert_eq!(
+            number_of_runs_to_keep(&runs, &config(None, None, None), 1000),
+            4
+        );
+        assert_eq!(
+            number_of_runs_to_keep(&runs, &config(Some(150), Some(3), None), 1000),
+            2
+        );
+        assert_eq!(
+            number_of_runs_to_keep(&runs, &config(None, Some(3), Some(250)), 1000),
+            2
+        );
+    }
+
+    #[test]
+    fn max_total_bytes_keeps_newest_run() {
+        let directory = tempfile::tempdir().unwrap();
+        let runs = runs(
+            Utf8Path::from_path(directory.path()).unwrap(),
+            &[(1000, 500), (900, 100)],
+        );
+        assert_eq!(number_of_runs_within_bytes(runs.iter(), 200), 1);
+    }
+
+    #[test]
+    fn failed_runs_are_recognized_by_marker() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = Utf8Path::from_path(directory.path()).unwrap();
+        assert!(!is_failed_run(directory));
+        write_run_outcome(directory, RunOutcome::AllTestsPassed).unwrap();
+        assert!(!is_failed_run(directory));
+        write_run_outcome(directory, RunOutcome::TimedOut).unwrap();
+        assert!(is_failed_run(directory));
+    }
+
+    #[test]
+    fn newest_runs_and_runs_without_outcome_are_not_archived() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = Utf8Path::from_path(directory.path()).unwrap();
+        let runs = runs(directory, &[(1000, 0), (900, 0), (800, 0), (700, 0)]);
+        for index in [0, 1, 3] {
+            write_run_outcome(&directory.join(index.to_string()), RunOutcome::TestFailures)
+                .unwrap();
+        }
+        assert_eq!(
+            runs_to_archive(runs, 50, 1000)
+                .map(|run| run.dir_entry.file_name().to_string())
+                .collect::<Vec<_>>(),
+            ["1", "3"]
+        );
+    }
 
     #[test]
     fn split_vec_at_zero() {
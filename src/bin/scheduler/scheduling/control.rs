@@ -0,0 +1,347 @@
+use crate::internal_config::Plan;
+use crate::logging::log_and_return_error;
+
+use anyhow::{Context, Result as AnyhowResult};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::{info, warn};
+use robotmk::control::{ControlRequest, ControlResponse, PlanStatus, control_socket_path};
+use robotmk::history;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio_util::sync::CancellationToken;
+
+// Shared between the control server and the plan group schedulers. On-demand runs are handed to
+// the scheduler of the plan's group, such that a plan never runs concurrently with itself.
+#[derive(Clone)]
+pub struct PlanControls {
+    plans: Arc<Mutex<HashMap<String, PlanControl>>>,
+}
+
+struct PlanControl {
+    paused: bool,
+    running: bool,
+    run_history_file: Utf8PathBuf,
+    run_requests: Option<UnboundedSender<String>>,
+}
+
+impl PlanControls {
+    pub fn new(plans: &[Plan]) -> Self {
+        Self {
+            plans: Arc::new(Mutex::new(
+                plans
+                    .iter()
+                    .map(|plan| {
+                        (
+                            plan.id.clone(),
+                            PlanControl {
+                                paused: false,
+                                running: false,
+                                run_history_file: plan.run_history_file.clone(),
+                                run_requests: None,
+                            },
+                        )
+                    })
+                    .collect(),
+            )),
+        }
+    }
+
+    pub fn register_group(&self, plans: &[Plan]) -> UnboundedReceiver<String> {
+        let (sender, receiver) = unbounded_channel();
+        let mut controls = self.plans.lock().unwrap();
+        for plan in plans {
+            if let Some(control) = controls.get_mut(&plan.id) {
+                control.run_requests = Some(sender.clone());
+            }
+        }
+        receiver
+    }
+
+    pub fn is_paused(&self, plan_id: &str) -> bool {
+        self.plans
+            .lock()
+            .unwrap()
+            .get(plan_id)
+            .is_some_and(|control| control.paused)
+    }
+
+    pub fn set_running(&self, plan_id: &str, running: bool) {
+        if let Some(control) = self.plans.lock().unwrap().get_mut(plan_id) {
+            control.running = running;
+        }
+    }
+
+    fn handle(&self, request: ControlRequest) -> ControlResponse {
+        let mut controls = self.plans.lock().unwrap();
+        let plan_id = match &request {
+            ControlRequest::Run { plan_id }
+            | ControlRequest::Pause { plan_id }
+            | ControlRequest::Resume { plan_id }
+            | ControlRequest::Status { plan_id } => plan_id.clone(),
+        };
+        let Some(control) = controls.get_mut(&plan_id) else {
+            return ControlResponse::Error(format!("Plan {plan_id} is not scheduled"));
+        };
+        match request {
+            ControlRequest::Run { .. } => match &control.run_requests {
+                Some(run_requests) if run_requests.send(plan_id.clone()).is_ok() => {
+                    info!("Plan {plan_id}: on-demand run requested");
+                    ControlResponse::Accepted
+                }
+                _ => ControlResponse::Error(format!("Plan {plan_id} cannot be run right now")),
+            },
+            ControlRequest::Pause { .. } => {
+                info!("Plan {plan_id}: paused");
+                control.paused = true;
+                ControlResponse::Accepted
+            }
+            ControlRequest::Resume { .. } => {
+                info!("Plan {plan_id}: resumed");
+                control.paused = false;
+                ControlResponse::Accepted
+            }
+            ControlRequest::Status { .. } => match history::latest(&control.run_history_file) {
+                Ok(last_run) => ControlResponse::Status(PlanStatus {
+                    plan_id,
+                    paused: control.paused,
+                    running: control.running,
+                    last_run,
+                }),
+                Err(error) => ControlResponse::Error(format!("{error:?}")),
+            },
+        }
+    }
+}
+
+pub async fn run_control_server(
+    controls: PlanControls,
+    runtime_directory: Utf8PathBuf,
+    cancellation_token: CancellationToken,
+) {
+    let socket_path = control_socket_path(&runtime_directory);
+    if let Err(error) = serve(&controls, &socket_path, &cancellation_token).await {
+        log_and_return_error(error.context(format!(
+            "Control server on {socket_path} failed, on-demand plan control is unavailable"
+        )));
+    }
+}
+
+#[cfg(unix)]
+async fn serve(
+    controls: &PlanControls,
+    socket_path: &Utf8Path,
+    cancellation_token: &CancellationToken,
+) -> AnyhowResult<()> {
+    use anyhow::bail;
+    use std::fs::{DirBuilder, Permissions, remove_file, set_permissions};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use tokio::net::{UnixListener, UnixStream};
+
+    if let Some(socket_directory) = socket_path.parent() {
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(socket_directory)
+            .context(format!("Failed to create {socket_directory}"))?;
+        // The directory might be left over from a previous run with different permissions.
+        set_permissions(socket_directory, Permissions::from_mode(0o700)).context(format!(
+            "Failed to restrict permissions of {socket_directory}"
+        ))?;
+    }
+    // Like the first pipe instance on Windows, we never take over the socket of a running
+    // scheduler. Only sockets nobody listens on anymore are left over from previous runs.
+    if socket_path.exists() {
+        if UnixStream::connect(socket_path).await.is_ok() {
+            bail!("Another scheduler is already listening on {socket_path}");
+        }
+        remove_file(socket_path).context(format!("Failed to remove stale socket {socket_path}"))?;
+    }
+    let listener =
+        UnixListener::bind(socket_path).context(format!("Failed to bind to {socket_path}"))?;
+    set_permissions(socket_path, Permissions::from_mode(0o600))
+        .context(format!("Failed to restrict permissions of {socket_path}"))?;
+    info!("Listening for control requests on {socket_path}");
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => { accepted.context("Failed to accept connection")?.0 }
+            _ = cancellation_token.cancelled() => { break }
+        };
+        tokio::spawn(handle_connection(controls.clone(), stream));
+    }
+    let _ = remove_file(socket_path);
+    Ok(())
+}
+
+#[cfg(windows)]
+async fn serve(
+    controls: &PlanControls,
+    socket_path: &Utf8Path,
+    cancellation_token: &CancellationToken,
+) -> AnyhowResult<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(socket_path)
+        .context(format!("Failed to create named pipe {socket_path}"))?;
+    info!("Listening for control requests on {socket_path}");
+    loop {
+        tokio::select! {
+            connected = server.connect() => { connected.context("Failed to accept connection")? }
+            _ = cancellation_token.cancelled() => { return Ok(()) }
+        };
+        let connected_server = server;
+        server = ServerOptions::new()
+            .create(socket_path)
+            .context(format!("Failed to create named pipe {socket_path}"))?;
+        tokio::spawn(handle_connection(controls.clone(), connected_server));
+    }
+}
+
+async fn handle_connection(controls: PlanControls, stream: impl AsyncRead + AsyncWrite + Unpin) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut line = String::new();
+    let response = match BufReader::new(reader).read_line(&mut line).await {
+        Ok(_) => match serde_json::from_str(&line) {
+            Ok(request) => controls.handle(request),
+            Err(error) => ControlResponse::Error(format!("Invalid request: {error}")),
+        },
+        Err(error) => {
+            warn!("Failed to read control request: {error}");
+            return;
+        }
+    };
+    let Ok(mut serialized) = serde_json::to_string(&response) else {
+        return;
+    };
+    serialized.push('\n');
+    if let Err(error) = writer.write_all(serialized.as_bytes()).await {
+        warn!("Failed to send control response: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controls(plan_ids: &[&str]) -> PlanControls {
+        PlanControls {
+            plans: Arc::new(Mutex::new(
+                plan_ids
+                    .iter()
+                    .map(|plan_id| {
+                        (
+                            plan_id.to_string(),
+                            PlanControl {
+                                paused: false,
+                                running: false,
+                                run_history_file: Utf8PathBuf::from(format!("{plan_id}.jsonl")),
+                                run_requests: None,
+                            },
+                        )
+                    })
+                    .collect(),
+            )),
+        }
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let controls = controls(&["login"]);
+        assert_eq!(
+            controls.handle(ControlRequest::Pause {
+                plan_id: "login".into()
+            }),
+            ControlResponse::Accepted
+        );
+        assert!(controls.is_paused("login"));
+        controls.handle(ControlRequest::Resume {
+            plan_id: "login".into(),
+        });
+        assert!(!controls.is_paused("login"));
+    }
+
+    #[test]
+    fn run_is_forwarded_to_group() {
+        let controls = controls(&["login"]);
+        let (sender, mut receiver) = unbounded_channel();
+        controls
+            .plans
+            .lock()
+            .unwrap()
+            .get_mut("login")
+            .unwrap()
+            .run_requests = Some(sender);
+        assert_eq!(
+            controls.handle(ControlRequest::Run {
+                plan_id: "login".into()
+            }),
+            ControlResponse::Accepted
+        );
+        assert_eq!(receiver.try_recv().unwrap(), "login");
+    }
+
+    #[test]
+    fn unknown_plan() {
+        assert_eq!(
+            controls(&["login"]).handle(ControlRequest::Status {
+                plan_id: "checkout".into()
+            }),
+            ControlResponse::Error("Plan checkout is not scheduled".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn connection_round_trip() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let handler = tokio::spawn(handle_connection(controls(&["login"]), server));
+        client
+            .write_all(b"{\"Pause\":{\"plan_id\":\"login\"}}\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        BufReader::new(client)
+            .read_line(&mut response)
+            .await
+            .unwrap();
+        handler.await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<ControlResponse>(&response).unwrap(),
+            ControlResponse::Accepted
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn socket_of_running_server_is_not_taken_over() {
+        let directory = tempfile::tempdir().unwrap();
+        let socket_path = Utf8Path::from_path(directory.path())
+            .unwrap()
+            .join("control")
+            .join("control.sock");
+        let cancellation_token = CancellationToken::new();
+        let controls = controls(&["login"]);
+        let running_server = tokio::spawn({
+            let (controls, socket_path, cancellation_token) = (
+                controls.clone(),
+                socket_path.clone(),
+                cancellation_token.clone(),
+            );
+            async move { serve(&controls, &socket_path, &cancellation_token).await }
+        });
+        while !socket_path.exists() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let error = serve(&controls, &socket_path, &cancellation_token)
+            .await
+            .unwrap_err();
+        assert!(format!("{error:?}").contains("Another scheduler is already listening"));
+
+        cancellation_token.cancel();
+        running_server.await.unwrap().unwrap();
+        assert!(!socket_path.exists());
+    }
+}
@@ -1,3 +1,5 @@
 mod cleanup;
+pub mod control;
 pub mod plans;
 pub mod scheduler;
+pub mod status;
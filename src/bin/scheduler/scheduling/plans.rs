@@ -1,23 +1,69 @@
-use crate::internal_config::{Plan, Source};
-use crate::logging::TIMESTAMP_FORMAT;
-use robotmk::plans::run_attempts_with_rebot;
-use robotmk::results::{AttemptsConfig, PlanExecutionReport};
+use crate::internal_config::{Plan, PlanDependency, Source};
+use crate::log_and_return_error;
+use crate::logging::{PLAN_LOG_FILE_NAME, TIMESTAMP_FORMAT};
+use robotmk::flakiness::update_flakiness_history;
+use robotmk::history::{self, RunOutcome, RunRecord};
+use robotmk::hooks::run_hooks;
+use robotmk::log_context;
+use robotmk::plans::{PlanRunSpec, run_attempts_with_rebot};
+use robotmk::results::{
+    AttemptReport, AttemptsConfig, FailedDependency, HookOutcome, HookStage, PlanActivity,
+    PlanExecutionReport, TestFlakiness,
+};
 
 use anyhow::Context;
+use camino::Utf8Path;
 use chrono::Utc;
-use log::info;
+use log::{error, info};
 use robotmk::section::WritePiggybackSection;
 use robotmk::termination::{ContextUnrecoverable, Terminate};
 use std::fs::create_dir_all;
 
-pub fn run_plan(plan: &Plan) -> Result<PlanExecutionReport, Terminate> {
+pub fn run_plan(plan: &Plan, write_plan_results: bool) -> Result<PlanExecutionReport, Terminate> {
+    let _log_context = log_context::scoped(|context| {
+        context.plan_id = Some(plan.id.clone());
+        context.session = Some(plan.session.id());
+    });
     info!(
         "Running plan {} ({})",
         &plan.id,
         format_source_for_logging(&plan.source)
     );
-    let report = produce_plan_results(plan)?;
+    plan.live_status.set_activity(
+        &plan.id,
+        PlanActivity::Running {
+            since: Utc::now().timestamp(),
+        },
+    );
+    let report = match failed_dependency(&plan.id, &plan.dependencies, Utc::now().timestamp()) {
+        Some(failed_dependency) => {
+            error!(
+                "Plan {}: latest run of prerequisite plan {} did not pass ({:?}), skipping run",
+                plan.id, failed_dependency.plan_id, failed_dependency.outcome
+            );
+            skipped_plan_results(plan, failed_dependency)
+        }
+        None => produce_plan_results(plan).inspect_err(|_| {
+            plan.live_status.set_activity(&plan.id, PlanActivity::Idle);
+        })?,
+    };
     info!("Plan {} finished", &plan.id);
+    let run_record = RunRecord::from(&report);
+    plan.live_status.update(&plan.id, |status| {
+        status.activity = PlanActivity::Idle;
+        status.last_outcome = Some(run_record.outcome);
+    });
+    // Runs without plan results (--no-plan-result) are not recorded, since dependent plans and the
+    // control interface would otherwise act on runs which were never reported.
+    if write_plan_results {
+        let _ = history::append(
+            &plan.run_history_file,
+            &run_record,
+            plan.run_history_max_runs,
+        )
+        .context(format!("Plan {}: failed to record run in history", plan.id))
+        .map_err(log_and_return_error);
+    }
     Ok(report)
 }
 
@@ -62,36 +108,165 @@ fn produce_plan_results(plan: &Plan) -> Result<PlanExecutionReport, Terminate> {
     create_dir_all(&output_directory).context(format!(
         "Failed to create directory for plan run: {output_directory}"
     ))?;
+    let _log_context = log_context::scoped(|context| {
+        context.log_file = Some(output_directory.join(PLAN_LOG_FILE_NAME));
+    });
 
-    let (attempt_reports, rebot) = run_attempts_with_rebot(
-        &plan.robot,
+    let mut hook_reports = run_hooks(
+        &plan.hooks.pre_run,
+        HookStage::PreRun,
         &plan.id,
         &plan.environment,
         &plan.session,
-        plan.timeout,
         &plan.cancellation_token,
         &output_directory,
     )
     .map_err(|cancelled| cancelled.into())
-    .context_unrecoverable("Received termination signal while running plan")?;
+    .context_unrecoverable("Received termination signal while running pre-run hooks")?;
+    let aborted_by_pre_run_hook = plan.hooks.fail_run_on_pre_run_hook_failure
+        && hook_reports
+            .iter()
+            .any(|hook_report| hook_report.outcome != HookOutcome::Success);
+
+    let (attempt_reports, rebot) = if aborted_by_pre_run_hook {
+        error!("Plan {}: pre-run hook failed, skipping attempts", plan.id);
+        (vec![], None)
+    } else {
+        run_attempts_with_rebot(&PlanRunSpec {
+            id: &plan.id,
+            robot: &plan.robot,
+            environment: &plan.environment,
+            session: &plan.session,
+            timeout: plan.timeout,
+            cancellation_token: &plan.cancellation_token,
+            output_directory: &output_directory,
+            report_activity: &|activity| plan.live_status.set_activity(&plan.id, activity),
+        })
+        .map_err(|cancelled| cancelled.into())
+        .context_unrecoverable("Received termination signal while running plan")?
+    };
+
+    let flakiness = track_flakiness(
+        plan,
+        &attempt_reports,
+        &output_directory,
+        timestamp.timestamp(),
+    );
+
+    hook_reports.extend(
+        run_hooks(
+            &plan.hooks.post_run,
+            HookStage::PostRun,
+            &plan.id,
+            &plan.environment,
+            &plan.session,
+            &plan.cancellation_token,
+            &output_directory,
+        )
+        .map_err(|cancelled| cancelled.into())
+        .context_unrecoverable("Received termination signal while running post-run hooks")?,
+    );
 
-    Ok(PlanExecutionReport {
+    let report = PlanExecutionReport {
         plan_id: plan.id.clone(),
         timestamp: timestamp.timestamp(),
         attempts: attempt_reports,
         rebot,
-        config: AttemptsConfig {
-            interval: plan.group_affiliation.execution_interval,
-            timeout: plan.timeout,
-            n_attempts_max: plan.robot.n_attempts_max,
+        config: attempts_config(plan),
+        metadata: plan.metadata.clone(),
+        hooks: hook_reports,
+        aborted_by_pre_run_hook,
+        flakiness,
+        failed_dependency: None,
+    };
+    let _ = history::write_run_outcome(&output_directory, RunRecord::from(&report).outcome)
+        .context(format!(
+            "Plan {}: failed to mark run with its outcome",
+            plan.id
+        ))
+        .map_err(log_and_return_error);
+    Ok(report)
+}
+
+// Prerequisite plans which have not run yet, or not recently, do not block their dependents.
+fn failed_dependency(
+    plan_id: &str,
+    dependencies: &[PlanDependency],
+    now: i64,
+) -> Option<FailedDependency> {
+    dependencies.iter().find_map(
+        |dependency| match history::latest(&dependency.run_history_file) {
+            Ok(Some(record))
+                if record.outcome != RunOutcome::AllTestsPassed
+                    && now.saturating_sub(record.timestamp)
+                        <= i64::try_from(dependency.max_record_age).unwrap_or(i64::MAX) =>
+            {
+                Some(FailedDependency {
+                    plan_id: dependency.plan_id.clone(),
+                    outcome: record.outcome,
+                    timestamp: record.timestamp,
+                })
+            }
+            Ok(_) => None,
+            Err(error) => {
+                log_and_return_error(error.context(format!(
+                    "Plan {plan_id}: failed to read run history of prerequisite plan {}",
+                    dependency.plan_id
+                )));
+                None
+            }
         },
+    )
+}
+
+fn skipped_plan_results(plan: &Plan, failed_dependency: FailedDependency) -> PlanExecutionReport {
+    PlanExecutionReport {
+        plan_id: plan.id.clone(),
+        timestamp: Utc::now().timestamp(),
+        attempts: vec![],
+        rebot: None,
+        config: attempts_config(plan),
         metadata: plan.metadata.clone(),
-    })
+        hooks: vec![],
+        aborted_by_pre_run_hook: false,
+        flakiness: vec![],
+        failed_dependency: Some(failed_dependency),
+    }
+}
+
+fn attempts_config(plan: &Plan) -> AttemptsConfig {
+    AttemptsConfig {
+        interval: plan.group_affiliation.execution_interval,
+        timeout: plan.timeout,
+        n_attempts_max: plan.robot.n_attempts_max,
+        total_budget: plan.robot.total_budget.clone(),
+    }
+}
+
+fn track_flakiness(
+    plan: &Plan,
+    attempt_reports: &[AttemptReport],
+    output_directory: &Utf8Path,
+    timestamp: i64,
+) -> Vec<TestFlakiness> {
+    let output_xml_files: Vec<_> = plan
+        .robot
+        .attempts(output_directory)
+        .take(attempt_reports.len())
+        .map(|attempt| attempt.output_xml_file)
+        .filter(|output_xml_file| output_xml_file.exists())
+        .collect();
+    update_flakiness_history(&plan.flakiness_history_file, timestamp, &output_xml_files)
+        .context(format!("Plan {}: failed to track flakiness", plan.id))
+        .map_err(log_and_return_error)
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use robotmk::config::PlanMetadata;
+    use robotmk::results::{AttemptOutcome, AttemptsConfig, RebotOutcome, RebotResult};
 
     #[test]
     fn format_source_for_logging_manual() {
@@ -123,4 +298,137 @@ mod tests {
             "managed robot, version: 4 (version_label)"
         );
     }
+
+    #[test]
+    fn failed_dependency_from_latest_run() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = Utf8Path::from_path(directory.path()).unwrap();
+        let dependency = |plan_id: &str| PlanDependency {
+            plan_id: plan_id.into(),
+            run_history_file: directory.join(format!("{plan_id}.jsonl")),
+            max_record_age: 100,
+        };
+        let record = |timestamp, outcome| RunRecord {
+            timestamp,
+            outcome,
+            attempts: 1,
+            runtime: 10,
+            tests: Default::default(),
+        };
+        history::append(
+            &dependency("login").run_history_file,
+            &record(1, RunOutcome::AllTestsPassed),
+            10,
+        )
+        .unwrap();
+        history::append(
+            &dependency("login").run_history_file,
+            &record(2, RunOutcome::TimedOut),
+            10,
+        )
+        .unwrap();
+        history::append(
+            &dependency("search").run_history_file,
+            &record(3, RunOutcome::AllTestsPassed),
+            10,
+        )
+        .unwrap();
+
+        assert_eq!(
+            failed_dependency(
+                "checkout",
+                &[dependency("never_ran"), dependency("search")],
+                50
+            ),
+            None
+        );
+        assert_eq!(
+            failed_dependency("checkout", &[dependency("search"), dependency("login")], 50),
+            Some(FailedDependency {
+                plan_id: "login".into(),
+                outcome: RunOutcome::TimedOut,
+                timestamp: 2,
+            })
+        );
+        assert_eq!(
+            failed_dependency(
+                "checkout",
+                &[dependency("search"), dependency("login")],
+                200
+            ),
+            None
+        );
+    }
+
+    // The prerequisite's last attempt only re-ran its tagged failure, which passed. The untagged
+    // failure from the first attempt is still part of the merged result.
+    #[test]
+    fn failed_dependency_after_incremental_retry() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = Utf8Path::from_path(directory.path()).unwrap();
+        let dependency = PlanDependency {
+            plan_id: "login".into(),
+            run_history_file: directory.join("login.jsonl"),
+            max_record_age: 100,
+        };
+        let attempt = |index, outcome| AttemptReport {
+            index,
+            outcome,
+            runtime: 5,
+            terminated_by: None,
+            resource_usage: None,
+        };
+        let report = PlanExecutionReport {
+            plan_id: "login".into(),
+            timestamp: 10,
+            attempts: vec![
+                attempt(1, AttemptOutcome::TestFailures),
+                attempt(2, AttemptOutcome::AllTestsPassed),
+            ],
+            rebot: Some(RebotOutcome::Ok(RebotResult {
+                xml: r#"<?xml version="1.0" encoding="UTF-8"?>
+<robot generator="Rebot 7.0 (Python 3.12.0 on linux)" rpa="false" schemaversion="5">
+<suite id="s1" name="Login">
+<test id="s1-t1" name="Tagged">
+<tag>retry</tag>
+<status status="PASS" start="2024-01-01T00:00:00.000000" elapsed="0.001"/>
+</test>
+<test id="s1-t2" name="Untagged">
+<status status="FAIL" start="2024-01-01T00:00:00.000000" elapsed="0.001"/>
+</test>
+<status status="FAIL" start="2024-01-01T00:00:00.000000" elapsed="0.002"/>
+</suite>
+</robot>
+"#
+                .into(),
+                html_base64: String::new(),
+                timestamp: 20,
+            })),
+            config: AttemptsConfig {
+                interval: 60,
+                timeout: 30,
+                n_attempts_max: 2,
+                total_budget: None,
+            },
+            metadata: PlanMetadata {
+                application: "app".into(),
+                suite_name: "login".into(),
+                variant: String::new(),
+            },
+            hooks: vec![],
+            aborted_by_pre_run_hook: false,
+            flakiness: vec![],
+            failed_dependency: None,
+        };
+        history::append(&dependency.run_history_file, &RunRecord::from(&report), 10).unwrap();
+
+        assert_eq!(
+            failed_dependency("checkout", &[dependency], 50),
+            Some(FailedDependency {
+                plan_id: "login".into(),
+                outcome: RunOutcome::TestFailures,
+                timestamp: 10,
+            })
+        );
+    }
 }
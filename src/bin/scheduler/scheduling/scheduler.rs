@@ -1,16 +1,23 @@
-use super::cleanup::cleanup_working_directories;
+use super::cleanup::cleanup;
+use super::control::{PlanControls, run_control_server};
 use super::plans::{run_plan, write_plan_result};
+use super::status::{SchedulingStatusReporter, TickTracker, unix_timestamp};
+use crate::heartbeat::{GroupProgress, Heartbeat};
 use crate::internal_config::{GlobalConfig, Plan};
 use crate::logging::log_and_return_error;
 
 use anyhow::anyhow;
 use chrono::Utc;
-use log::info;
-use robotmk::termination::Terminate;
+use log::{info, warn};
+use robotmk::config::{GroupExecutionMode, MissedTickPolicy};
+use robotmk::session::Session;
+use robotmk::termination::{Cancelled, Terminate};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore, watch};
 use tokio::task::{JoinSet, spawn_blocking};
-use tokio::time::{Instant, interval_at};
+use tokio::time::{Instant, MissedTickBehavior, interval_at};
 use tokio_util::sync::CancellationToken;
 
 #[tokio::main]
@@ -18,6 +25,7 @@ pub async fn run_plans_and_cleanup(
     global_config: &GlobalConfig,
     plans: &[Plan],
     write_plan_results: bool,
+    heartbeat: &Heartbeat,
 ) {
     let mut plans_by_exec_group = HashMap::new();
     for plan in plans {
@@ -30,22 +38,33 @@ pub async fn run_plans_and_cleanup(
             .push(plan.clone());
     }
 
+    let status_reporter = SchedulingStatusReporter::new(
+        &global_config.results_directory,
+        &global_config.results_directory_locker,
+    );
+    let controls = PlanControls::new(plans);
     let mut join_set = JoinSet::new();
-    for ((_, execution_interval), mut plans) in plans_by_exec_group {
+    for ((group_index, execution_interval), mut plans) in plans_by_exec_group {
         plans.sort_by_key(|plan| plan.group_affiliation.position_in_group);
         join_set.spawn(run_sequential_plan_group_scheduler(
             execution_interval,
             plans,
             global_config.cancellation_token.clone(),
             write_plan_results,
+            status_reporter.clone(),
+            controls.clone(),
+            heartbeat.group_progress(group_index),
         ));
     }
 
-    join_set.spawn(run_cleanup_job(
+    join_set.spawn(run_control_server(
+        controls,
+        global_config.runtime_base_directory.clone(),
         global_config.cancellation_token.clone(),
-        plans.to_vec(),
     ));
 
+    join_set.spawn(run_cleanup_job(global_config.clone(), plans.to_vec()));
+
     global_config.cancellation_token.cancelled().await;
     info!("Received termination signal while scheduling, waiting for plans to terminate");
     while let Some(outcome) = join_set.join_next().await {
@@ -60,65 +79,289 @@ async fn run_sequential_plan_group_scheduler(
     plans: Vec<Plan>,
     cancellation_token: CancellationToken,
     write_plan_results: bool,
+    status_reporter: SchedulingStatusReporter,
+    controls: PlanControls,
+    progress: GroupProgress,
 ) {
-    // It is debatable whether MissedTickBehavior::Burst (the default) is correct. In practice, as
-    // long as timeout * number of attempts is shorter than the execution interval, it shouldn't
-    // make a difference anyway.  However, in case we consider changing this, note that using
-    // `MissedTickBehavior::Delay` leads to a strange sort of lag on Windows (as if we added ~10 ms
-    // to the scheduling interval). See also:
+    // MissedTickBehavior::Burst is the default. In practice, as long as timeout * number of
+    // attempts is shorter than the execution interval, the policy doesn't make a difference
+    // anyway. Note that `MissedTickBehavior::Delay` leads to a strange sort of lag on Windows (as
+    // if we added ~10 ms to the scheduling interval). See also:
     // https://www.reddit.com/r/rust/comments/13yymkh/weird_tokiotimeinterval_tick_behavior/
     // https://github.com/tokio-rs/tokio/issues/5021
-    let mut clock = interval_at(compute_start_time(interval), Duration::from_secs(interval));
+    let Some((missed_tick_policy, execution_mode)) = plans.first().map(|plan| {
+        (
+            plan.group_affiliation.missed_tick_policy,
+            plan.group_affiliation.execution_mode,
+        )
+    }) else {
+        return;
+    };
+    let period = Duration::from_secs(interval);
+    let start_time = compute_start_time(interval);
+    let mut clock = interval_at(start_time, period);
+    clock.set_missed_tick_behavior(match missed_tick_policy {
+        MissedTickPolicy::Burst => MissedTickBehavior::Burst,
+        MissedTickPolicy::Delay => MissedTickBehavior::Delay,
+        MissedTickPolicy::Skip => MissedTickBehavior::Skip,
+    });
+    let mut tick_tracker = TickTracker::new(start_time, period);
+    let group_description = plans
+        .iter()
+        .map(|plan| plan.id.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut run_requests = controls.register_group(&plans);
+    report_next_scheduled_run(&plans, start_time).await;
     loop {
-        tokio::select! {
-            _ = clock.tick() => { }
+        let scheduled = tokio::select! {
+            scheduled = clock.tick() => { scheduled }
+            Some(plan_id) = run_requests.recv() => {
+                if let Some(plan) = plans.iter().find(|plan| plan.id == plan_id) {
+                    info!("Plan {plan_id}: running on demand");
+                    if let Err(Cancelled) =
+                        run_plan_to_completion(plan.clone(), write_plan_results, &controls).await
+                    {
+                        return;
+                    }
+                    progress.record();
+                }
+                continue;
+            }
             _ = cancellation_token.cancelled() => { return }
         };
-        for plan in plans.clone() {
-            let plan_id = plan.id.clone();
-            match spawn_blocking(move || match run_plan(&plan) {
-                Ok(report) => {
-                    if write_plan_results {
-                        if let Err(e) = write_plan_result(&plan, &report) {
-                            Err(log_and_return_error(e))
-                        } else {
-                            Ok(())
-                        }
-                    } else {
-                        info!(
-                            "--no-plan-result specified: skipping writing plan result for {}",
-                            plan.id
-                        );
-                        Ok(())
-                    }
+        let actual_start = Instant::now();
+        let tick = tick_tracker.record(scheduled, actual_start);
+        if tick.skipped_ticks > 0 {
+            warn!(
+                "Plan group ({group_description}): skipped {} scheduled run(s)",
+                tick.skipped_ticks
+            );
+        }
+        if tick.lag >= Duration::from_secs(1) {
+            warn!(
+                "Plan group ({group_description}): started {}s behind schedule",
+                tick.lag.as_secs()
+            );
+        }
+        report_next_scheduled_run(
+            &plans,
+            match missed_tick_policy {
+                MissedTickPolicy::Delay => actual_start + period,
+                MissedTickPolicy::Burst | MissedTickPolicy::Skip => tick.intended_start + period,
+            },
+        )
+        .await;
+        status_reporter
+            .update(&plans, move |status| {
+                status.last_intended_start = Some(unix_timestamp(tick.intended_start));
+                status.last_actual_start = Some(unix_timestamp(actual_start));
+                status.last_lag_millis = tick.lag.as_millis() as u64;
+                status.max_lag_millis = status.max_lag_millis.max(status.last_lag_millis);
+                status.skipped_ticks += tick.skipped_ticks;
+            })
+            .await;
+        let active_plans: Vec<Plan> = plans
+            .iter()
+            .filter(|plan| {
+                let paused = controls.is_paused(&plan.id);
+                if paused {
+                    info!("Plan {}: paused, skipping scheduled run", plan.id);
                 }
-                Err(e) => Err(log_and_return_error(e)),
+                !paused
             })
-            .await
-            {
-                Ok(Err(Terminate::Cancelled)) => {
-                    return;
+            .cloned()
+            .collect();
+        let outcome = match execution_mode {
+            GroupExecutionMode::Sequential => {
+                run_plans_sequentially(&active_plans, write_plan_results, &controls).await
+            }
+            GroupExecutionMode::Parallel { max_concurrency } => {
+                run_plans_in_parallel(
+                    &active_plans,
+                    max_concurrency,
+                    write_plan_results,
+                    &controls,
+                )
+                .await
+            }
+        };
+        if let Err(Cancelled) = outcome {
+            return;
+        }
+        progress.record();
+        let run_duration = actual_start.elapsed();
+        let overrun = run_duration > period;
+        if overrun {
+            warn!(
+                "Plan group ({group_description}): run took {}s, which exceeds the execution interval of {interval}s",
+                run_duration.as_secs()
+            );
+        }
+        status_reporter
+            .update(&plans, move |status| {
+                status.last_run_duration = Some(run_duration.as_secs());
+                if overrun {
+                    status.overruns += 1;
                 }
-                Err(error) => {
-                    log_and_return_error(anyhow!(error).context(format!(
-                        "Task for plan {plan_id} failed to execute to completion"
-                    )));
+            })
+            .await;
+    }
+}
+
+async fn report_next_scheduled_run(plans: &[Plan], next_run: Instant) {
+    let plans = plans.to_vec();
+    let next_run = unix_timestamp(next_run);
+    if let Err(error) = spawn_blocking(move || {
+        for plan in plans {
+            plan.live_status.update(&plan.id, |status| {
+                status.next_scheduled_run = Some(next_run)
+            });
+        }
+    })
+    .await
+    {
+        log_and_return_error(anyhow!(error).context("Task for writing live status failed"));
+    }
+}
+
+async fn run_plans_sequentially(
+    plans: &[Plan],
+    write_plan_results: bool,
+    controls: &PlanControls,
+) -> Result<(), Cancelled> {
+    for plan in plans.iter().cloned() {
+        run_plan_to_completion(plan, write_plan_results, controls).await?;
+    }
+    Ok(())
+}
+
+// Plans are spawned in the order of the group and at most `max_concurrency` of them run at a
+// time. The order in which waiting plans obtain a free slot is not guaranteed. Plans running
+// in the same user session are never executed concurrently, since they would compete for the same
+// desktop. A plan depending on another plan of the group waits for the prerequisite to finish,
+// regardless of their positions (dependency cycles are rejected during configuration). Dependencies
+// on plans in other groups are evaluated against the latest available result instead.
+async fn run_plans_in_parallel(
+    plans: &[Plan],
+    max_concurrency: usize,
+    write_plan_results: bool,
+    controls: &PlanControls,
+) -> Result<(), Cancelled> {
+    let semaphore = Arc::new(Semaphore::new(max_concurrency.max(1)));
+    let mut session_locks = HashMap::new();
+    let (mut completion_senders, completions): (HashMap<_, _>, HashMap<_, _>) = plans
+        .iter()
+        .map(|plan| {
+            let (sender, receiver) = watch::channel(false);
+            ((plan.id.clone(), sender), (plan.id.clone(), receiver))
+        })
+        .unzip();
+    let mut join_set = JoinSet::new();
+    for plan in plans.iter().cloned() {
+        let prerequisites: Vec<watch::Receiver<bool>> = plan
+            .dependencies
+            .iter()
+            .filter_map(|dependency| completions.get(&dependency.plan_id).cloned())
+            .collect();
+        let Some(completion_sender) = completion_senders.remove(&plan.id) else {
+            continue;
+        };
+        let session_lock = match &plan.session {
+            Session::Current(_) => None,
+            Session::User(_) => Some(
+                session_locks
+                    .entry(plan.session.id())
+                    .or_insert_with(|| Arc::new(Mutex::new(())))
+                    .clone(),
+            ),
+        };
+        let semaphore = semaphore.clone();
+        let controls = controls.clone();
+        join_set.spawn(async move {
+            for mut prerequisite in prerequisites {
+                // An error means the prerequisite task ended without reporting completion.
+                let _ = prerequisite.wait_for(|completed| *completed).await;
+            }
+            // Acquire the session first to avoid occupying a slot while waiting for the session.
+            let _session_guard = match &session_lock {
+                Some(session_lock) => Some(session_lock.lock().await),
+                None => None,
+            };
+            let _permit = semaphore.acquire().await;
+            let outcome = run_plan_to_completion(plan, write_plan_results, &controls).await;
+            let _ = completion_sender.send(true);
+            outcome
+        });
+    }
+    let mut outcome = Ok(());
+    while let Some(task_outcome) = join_set.join_next().await {
+        match task_outcome {
+            Ok(Err(Cancelled)) => outcome = Err(Cancelled),
+            Err(error) => {
+                log_and_return_error(
+                    anyhow!(error).context("Task for parallel plan execution failed"),
+                );
+            }
+            _ => {}
+        }
+    }
+    outcome
+}
+
+async fn run_plan_to_completion(
+    plan: Plan,
+    write_plan_results: bool,
+    controls: &PlanControls,
+) -> Result<(), Cancelled> {
+    let plan_id = plan.id.clone();
+    controls.set_running(&plan_id, true);
+    let outcome = spawn_blocking(move || match run_plan(&plan, write_plan_results) {
+        Ok(report) => {
+            if write_plan_results {
+                if let Err(e) = write_plan_result(&plan, &report) {
+                    Err(log_and_return_error(e))
+                } else {
+                    Ok(())
                 }
-                _ => {}
+            } else {
+                info!(
+                    "--no-plan-result specified: skipping writing plan result for {}",
+                    plan.id
+                );
+                Ok(())
             }
         }
+        Err(e) => Err(log_and_return_error(e)),
+    })
+    .await;
+    controls.set_running(&plan_id, false);
+    match outcome {
+        Ok(Err(Terminate::Cancelled)) => Err(Cancelled),
+        Err(error) => {
+            log_and_return_error(anyhow!(error).context(format!(
+                "Task for plan {plan_id} failed to execute to completion"
+            )));
+            Ok(())
+        }
+        _ => Ok(()),
     }
 }
 
-async fn run_cleanup_job(cancellation_token: CancellationToken, plans: Vec<Plan>) {
-    let mut clock = interval_at(compute_start_time(300), Duration::from_secs(300));
+async fn run_cleanup_job(global_config: GlobalConfig, plans: Vec<Plan>) {
+    let interval_secs = global_config.cleanup_config.interval_secs;
+    let mut clock = interval_at(
+        compute_start_time(interval_secs),
+        Duration::from_secs(interval_secs),
+    );
     loop {
         let plans = plans.clone();
+        let global_config = global_config.clone();
         tokio::select! {
             _ = clock.tick() => { }
-            _ = cancellation_token.cancelled() => { return }
+            _ = global_config.cancellation_token.cancelled() => { return }
         };
-        let _ = spawn_blocking(move || cleanup_working_directories(plans.iter()))
+        let _ = spawn_blocking(move || cleanup(&global_config, &plans))
             .await
             .map_err(|err| {
                 log_and_return_error(
@@ -0,0 +1,186 @@
+use crate::internal_config::Plan;
+use crate::logging::log_and_return_error;
+use crate::reporter::SectionReporter;
+
+use anyhow::anyhow;
+use camino::Utf8Path;
+use chrono::Utc;
+use robotmk::lock::Locker;
+use robotmk::results::{GroupSchedulingStatus, SchedulingStatus};
+use std::time::Duration;
+use tokio::task::spawn_blocking;
+use tokio::time::Instant;
+
+// Shared by all plan group schedulers. Every update rewrites the whole section.
+#[derive(Clone)]
+pub struct SchedulingStatusReporter(SectionReporter<usize, GroupSchedulingStatus>);
+
+impl SchedulingStatusReporter {
+    pub fn new(results_directory: &Utf8Path, locker: &Locker) -> Self {
+        Self(SectionReporter::new(
+            results_directory.join("scheduling_status.json"),
+            locker,
+            "scheduling status",
+        ))
+    }
+
+    pub async fn update(
+        &self,
+        plans: &[Plan],
+        update: impl FnOnce(&mut GroupSchedulingStatus) + Send + 'static,
+    ) {
+        let Some(first_plan) = plans.first() else {
+            return;
+        };
+        let group_index = first_plan.group_affiliation.group_index;
+        let initial = GroupSchedulingStatus {
+            plan_ids: plans.iter().map(|plan| plan.id.clone()).collect(),
+            execution_interval: first_plan.group_affiliation.execution_interval,
+            missed_tick_policy: first_plan.group_affiliation.missed_tick_policy,
+            last_intended_start: None,
+            last_actual_start: None,
+            last_lag_millis: 0,
+            max_lag_millis: 0,
+            last_run_duration: None,
+            overruns: 0,
+            skipped_ticks: 0,
+        };
+        let reporter = self.0.clone();
+        if let Err(error) = spawn_blocking(move || {
+            reporter.update(group_index, || initial, update, SchedulingStatus)
+        })
+        .await
+        {
+            log_and_return_error(
+                anyhow!(error).context("Task for writing scheduling status failed"),
+            );
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Tick {
+    pub intended_start: Instant,
+    pub lag: Duration,
+    pub skipped_ticks: u64,
+}
+
+// Relates the ticks of a plan group scheduler to the intended schedule, which consists of
+// multiples of the execution interval after the first tick.
+pub struct TickTracker {
+    start: Instant,
+    interval: Duration,
+    last_tick_index: Option<u64>,
+}
+
+impl TickTracker {
+    pub fn new(start: Instant, interval: Duration) -> Self {
+        Self {
+            start,
+            interval,
+            last_tick_index: None,
+        }
+    }
+
+    pub fn record(&mut self, scheduled: Instant, actual: Instant) -> Tick {
+        let tick_index = u64::try_from(
+            scheduled.duration_since(self.start).as_millis() / self.interval.as_millis().max(1),
+        )
+        .unwrap_or(u64::MAX);
+        let skipped_ticks = match self.last_tick_index {
+            Some(last_tick_index) => tick_index.saturating_sub(last_tick_index + 1),
+            None => tick_index,
+        };
+        self.last_tick_index = Some(tick_index);
+        let intended_start = self.start
+            + self
+                .interval
+                .saturating_mul(u32::try_from(tick_index).unwrap_or(u32::MAX));
+        Tick {
+            intended_start,
+            lag: actual.duration_since(intended_start),
+            skipped_ticks,
+        }
+    }
+}
+
+pub fn unix_timestamp(instant: Instant) -> i64 {
+    let now = Instant::now();
+    let now_unix = Utc::now().timestamp_millis();
+    (if instant <= now {
+        now_unix - now.duration_since(instant).as_millis() as i64
+    } else {
+        now_unix + instant.duration_since(now).as_millis() as i64
+    }) / 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_tracker_burst() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(60);
+        let mut tracker = TickTracker::new(start, interval);
+        assert_eq!(
+            tracker.record(start, start),
+            Tick {
+                intended_start: start,
+                lag: Duration::ZERO,
+                skipped_ticks: 0
+            }
+        );
+        // The first run took 150s, so the two missed ticks fire immediately.
+        let actual = start + Duration::from_secs(150);
+        assert_eq!(
+            tracker.record(start + interval, actual),
+            Tick {
+                intended_start: start + interval,
+                lag: Duration::from_secs(90),
+                skipped_ticks: 0
+            }
+        );
+        assert_eq!(
+            tracker.record(start + interval * 2, actual).lag,
+            Duration::from_secs(30)
+        );
+    }
+
+    #[test]
+    fn tick_tracker_skip() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(60);
+        let mut tracker = TickTracker::new(start, interval);
+        tracker.record(start, start);
+        tracker.record(start + interval, start + Duration::from_secs(150));
+        assert_eq!(
+            tracker.record(start + interval * 3, start + interval * 3),
+            Tick {
+                intended_start: start + interval * 3,
+                lag: Duration::ZERO,
+                skipped_ticks: 1
+            }
+        );
+    }
+
+    #[test]
+    fn tick_tracker_delay() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(60);
+        let mut tracker = TickTracker::new(start, interval);
+        tracker.record(start, start);
+        tracker.record(start + interval, start + Duration::from_secs(150));
+        assert_eq!(
+            tracker.record(
+                start + Duration::from_secs(210),
+                start + Duration::from_secs(210)
+            ),
+            Tick {
+                intended_start: start + interval * 3,
+                lag: Duration::from_secs(30),
+                skipped_ticks: 1
+            }
+        );
+    }
+}
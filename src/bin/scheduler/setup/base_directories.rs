@@ -1,14 +1,23 @@
-use super::fs_entries::{clean_up_file_system_entries, top_level_directories, top_level_files};
+use super::fs_entries::{
+    clean_up_file_system_entries, top_level_directories, top_level_directory_entries,
+    top_level_files,
+};
 use super::ownership::transfer_directory_ownership_recursive;
 #[cfg(windows)]
 use super::windows_permissions::reset_access;
 
-use crate::internal_config::{GlobalConfig, Plan};
+use crate::internal_config::{
+    GlobalConfig, Plan, Source, managed_previous_directory, managed_previous_version_record,
+    managed_staging_directory, managed_version_record,
+};
 
 use anyhow::Result as AnyhowResult;
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
+use robotmk::flakiness::flakiness_history_directory;
 use robotmk::fs::{create_dir_all, remove_dir_all, remove_file};
-use robotmk::results::plan_results_directory;
+use robotmk::history::run_history_directory;
+use robotmk::process_registry::{self, process_registry_directory};
+use robotmk::results::{plan_results_directory, scheduler_heartbeat_file};
 use robotmk::termination::{ContextUnrecoverable, Terminate};
 
 pub fn setup(global_config: &GlobalConfig, plans: &[Plan]) -> Result<(), Terminate> {
@@ -17,13 +26,25 @@ pub fn setup(global_config: &GlobalConfig, plans: &[Plan]) -> Result<(), Termina
     #[cfg(windows)]
     reset_access(&global_config.runtime_base_directory)?;
 
+    setup_process_registry(&global_config.state_directory)?;
     setup_working_directory(global_config, plans)?;
-    setup_managed_directory(&global_config.managed_directory)?;
+    setup_managed_directory(&global_config.managed_directory, plans)?;
+    setup_state_directory(&global_config.state_directory, plans)?;
     setup_results_directory(global_config, plans)?;
 
     Ok(())
 }
 
+// Processes which survived a crash of the previous scheduler run are terminated before we clean up
+// the directories they might still be using.
+fn setup_process_registry(state_directory: &Utf8Path) -> AnyhowResult<()> {
+    let registry_directory = process_registry_directory(state_directory);
+    process_registry::reap_orphans(&registry_directory)?;
+    create_dir_all(&registry_directory)?;
+    process_registry::init(&registry_directory);
+    Ok(())
+}
+
 fn setup_working_directory(global_config: &GlobalConfig, plans: &[Plan]) -> AnyhowResult<()> {
     create_dir_all(&global_config.working_directory)?;
     create_dir_all(&global_config.working_directory_plans)?;
@@ -43,12 +64,43 @@ fn setup_working_directory(global_config: &GlobalConfig, plans: &[Plan]) -> Anyh
     Ok(())
 }
 
-fn setup_managed_directory(managed_directory: &Utf8Path) -> AnyhowResult<()> {
-    if managed_directory.exists() {
-        remove_dir_all(managed_directory)?;
-    }
+// Unpacked robots are kept across restarts, such that unchanged robots do not have to be unpacked
+// again. We only remove what belongs to robots which are not configured anymore.
+fn setup_managed_directory(managed_directory: &Utf8Path, plans: &[Plan]) -> AnyhowResult<()> {
     create_dir_all(managed_directory)?;
-    Ok(())
+    let mut entries_to_keep: Vec<Utf8PathBuf> = vec![];
+    for plan in plans {
+        if let Source::Managed { target, .. } = &plan.source {
+            entries_to_keep.extend([
+                target.clone(),
+                managed_staging_directory(target),
+                managed_previous_directory(target),
+                managed_version_record(target),
+                managed_previous_version_record(target),
+            ]);
+        }
+    }
+    clean_up_file_system_entries(
+        entries_to_keep,
+        top_level_directory_entries(managed_directory)?,
+    )
+}
+
+// The state directory holds data which must survive restarts, such as the flakiness and run
+// histories. We only remove what belongs to plans which are not configured anymore.
+fn setup_state_directory(state_directory: &Utf8Path, plans: &[Plan]) -> AnyhowResult<()> {
+    let flakiness_directory = flakiness_history_directory(state_directory);
+    create_dir_all(&flakiness_directory)?;
+    clean_up_file_system_entries(
+        plans.iter().map(|plan| &plan.flakiness_history_file),
+        top_level_directory_entries(&flakiness_directory)?.iter(),
+    )?;
+    let run_history_directory = run_history_directory(state_directory);
+    create_dir_all(&run_history_directory)?;
+    clean_up_file_system_entries(
+        plans.iter().map(|plan| &plan.run_history_file),
+        top_level_directory_entries(&run_history_directory)?.iter(),
+    )
 }
 
 fn setup_results_directory(global_config: &GlobalConfig, plans: &[Plan]) -> Result<(), Terminate> {
@@ -65,8 +117,12 @@ fn clean_up_results_directory(
     let results_directory_lock = global_config
         .results_directory_locker
         .wait_for_write_lock()?;
+    // The heartbeat is already being written at this point
+    let heartbeat_file = scheduler_heartbeat_file(&global_config.results_directory);
     for path in top_level_files(&global_config.results_directory)? {
-        remove_file(path)?;
+        if path != heartbeat_file {
+            remove_file(path)?;
+        }
     }
     clean_up_file_system_entries(
         plans.iter().map(|plan| &plan.results_file),
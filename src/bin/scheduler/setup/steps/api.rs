@@ -90,8 +90,14 @@ mod tests {
 
     use super::*;
     use crate::internal_config::{GroupAffiliation, Source};
-    use robotmk::config::{PlanMetadata, RetryStrategy, WorkingDirectoryCleanupConfig};
+    use crate::live_status::LiveStatusReporter;
+    use robotmk::config::{
+        DryRunValidationConfig, GroupExecutionMode, MissedTickPolicy, PlanMetadata,
+        RetryPolicyConfig, RetryStrategy, RobotExecutionMode, TerminationLadder,
+        WorkingDirectoryCleanupConfig,
+    };
     use robotmk::env::{Environment, system::SystemEnvironment};
+    use robotmk::hooks::Hooks;
     use robotmk::lock::Locker;
     use robotmk::rf::robot::Robot;
     use robotmk::section::Host;
@@ -129,6 +135,9 @@ mod tests {
             source: Source::Manual,
             working_directory: Utf8PathBuf::default(),
             results_file: Utf8PathBuf::default(),
+            flakiness_history_file: Utf8PathBuf::default(),
+            run_history_file: Utf8PathBuf::default(),
+            run_history_max_runs: usize::default(),
             timeout: u64::default(),
             robot: Robot {
                 robot_target: Utf8PathBuf::default(),
@@ -136,6 +145,10 @@ mod tests {
                 envs_rendered_obfuscated: Vec::default(),
                 n_attempts_max: usize::default(),
                 retry_strategy: RetryStrategy::Incremental,
+                execution_mode: RobotExecutionMode::Robot,
+                retry_policy: RetryPolicyConfig::default(),
+                total_budget: None,
+                termination_ladder: TerminationLadder::default(),
             },
             environment: Environment::System(SystemEnvironment {}),
             session: Session::Current(CurrentSession {}),
@@ -154,7 +167,17 @@ mod tests {
                 group_index: usize::default(),
                 position_in_group: usize::default(),
                 execution_interval: u64::default(),
+                missed_tick_policy: MissedTickPolicy::Burst,
+                execution_mode: GroupExecutionMode::Sequential,
             },
+            hooks: Hooks::default(),
+            dry_run_validation: DryRunValidationConfig::Disabled,
+            dependencies: vec![],
+            failed_run_cleanup_config: None,
+            live_status: LiveStatusReporter::new(
+                &Utf8PathBuf::default(),
+                &Locker::new(Utf8PathBuf::default(), None),
+            ),
         };
         let mut plan_ok = plan_bluerpint.clone();
         plan_ok.id = "ok".into();
@@ -4,13 +4,16 @@ use super::{
     rcc_working_directory_for_session,
 };
 
-use crate::internal_config::{GlobalConfig, Plan, Source};
+use crate::internal_config::{
+    GlobalConfig, Plan, Source, dry_run_directory, managed_staging_directory,
+};
 #[cfg(windows)]
 use crate::setup::ownership::transfer_directory_ownership_recursive;
 #[cfg(windows)]
 use crate::setup::windows_permissions::{grant_full_access, reset_access, run_icacls_command};
 
 use camino::Utf8PathBuf;
+use robotmk::config::DryRunValidationConfig;
 use robotmk::env::Environment;
 use robotmk::fs::create_dir_all;
 use robotmk::session::Session;
@@ -422,6 +425,29 @@ pub fn gather_conda_environment_building_directories(
     setup_steps
 }
 
+// The dry run is executed in the plan's session, which must be able to write its output.
+pub fn gather_dry_run_directories(config: &GlobalConfig, plans: Vec<Plan>) -> Vec<StepWithPlans> {
+    let mut setup_steps: Vec<StepWithPlans> = Vec::new();
+    let mut unaffected_plans = Vec::new();
+    for plan in plans.into_iter() {
+        match &plan.dry_run_validation {
+            DryRunValidationConfig::Enabled { .. } => setup_steps.push((
+                Box::new(StepCreateWithAccess {
+                    target: dry_run_directory(
+                        &config.working_directory_environment_building,
+                        &plan.id,
+                    ),
+                    session: plan.session.clone(),
+                }),
+                vec![plan],
+            )),
+            DryRunValidationConfig::Disabled => unaffected_plans.push(plan),
+        }
+    }
+    setup_steps.push(skip(unaffected_plans));
+    setup_steps
+}
+
 pub fn gather_rcc_working_base(config: &GlobalConfig, plans: Vec<Plan>) -> Vec<StepWithPlans> {
     let (rcc_plans, system_plans): (Vec<Plan>, Vec<Plan>) =
         partition_into_rcc_and_other_plans(plans);
@@ -465,7 +491,7 @@ pub fn gather_managed_directories(
         if let Source::Managed { target, .. } = &plan.source {
             setup_steps.push((
                 Box::new(StepCreateWithAccess {
-                    target: target.clone(),
+                    target: managed_staging_directory(target),
                     session: plan.session.clone(),
                 }),
                 vec![plan],
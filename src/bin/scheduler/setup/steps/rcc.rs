@@ -8,7 +8,7 @@ use crate::logging::log_and_return_error;
 #[cfg(windows)]
 use crate::setup::windows_permissions::run_icacls_command;
 
-use robotmk::config::RCCProfileConfig;
+use robotmk::config::{RCCProfileConfig, TerminationLadder};
 use robotmk::env::rcc::RCCEnvironment;
 use robotmk::session::{RunSpec, Session};
 use robotmk::termination::Outcome;
@@ -116,6 +116,7 @@ impl SetupStep for StepRCCCommand {
             .join(&self.id),
             timeout: 120,
             cancellation_token: &self.cancellation_token,
+            termination_ladder: &TerminationLadder::default(),
         };
         let run_outcome = match self.session.run(&run_spec).context(format!(
             "Failed to run {} for `{}`",
@@ -200,6 +201,7 @@ impl SetupStep for StepDisableSharedHolotree {
             .join(name),
             timeout: 120,
             cancellation_token: &self.cancellation_token,
+            termination_ladder: &TerminationLadder::default(),
         };
         match self.session.run(run_spec) {
             Ok(Outcome::Completed(0)) => Ok(()),
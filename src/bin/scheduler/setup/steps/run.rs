@@ -31,9 +31,9 @@ pub fn run(
 
 type Gatherer = fn(&GlobalConfig, Vec<Plan>) -> Vec<StepWithPlans>;
 #[cfg(unix)]
-type Steps = [(Gatherer, &'static str); 14];
+type Steps = [(Gatherer, &'static str); 15];
 #[cfg(windows)]
-type Steps = [(Gatherer, &'static str); 21];
+type Steps = [(Gatherer, &'static str); 22];
 
 const STEPS: Steps = [
     #[cfg(windows)]
@@ -90,6 +90,10 @@ const STEPS: Steps = [
         directories::gather_conda_environment_building_directories,
         "Conda environment building directories",
     ),
+    (
+        directories::gather_dry_run_directories,
+        "Dry run directories",
+    ),
     (
         directories::gather_rcc_working_base,
         "Base working directory for RCC setup steps",
@@ -1,10 +1,18 @@
 use super::api::{self, SetupStep, StepWithPlans, skip};
-use crate::internal_config::{GlobalConfig, Plan, Source};
+use crate::internal_config::{
+    GlobalConfig, Plan, Source, managed_previous_directory, managed_previous_version_record,
+    managed_staging_directory, managed_version_record,
+};
+use crate::setup::fs_entries::top_level_directory_entries;
 use anyhow::Context;
 use camino::{Utf8Path, Utf8PathBuf};
 use flate2::read::GzDecoder;
 use log::info;
-use std::fs::File;
+use robotmk::fs::{remove_dir_all, remove_file};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{File, read_to_string, rename, write};
+use std::io;
 use tar::Archive;
 
 const SIZE_LIMIT: u64 = 50 * 1024 * 1024;
@@ -17,11 +25,15 @@ pub fn gather(_config: &GlobalConfig, plans: Vec<Plan>) -> Vec<StepWithPlans> {
             Source::Managed {
                 tar_gz_path,
                 target,
-                ..
+                version_number,
+                version_label,
             } => steps.push((
                 Box::new(StepUnpackManaged {
                     tar_gz_path: tar_gz_path.clone(),
                     target_dir: target.clone(),
+                    version_number: *version_number,
+                    version_label: version_label.clone(),
+                    session: plan.session.id(),
                     size_limit: SIZE_LIMIT,
                 }),
                 vec![plan],
@@ -38,6 +50,9 @@ pub fn gather(_config: &GlobalConfig, plans: Vec<Plan>) -> Vec<StepWithPlans> {
 struct StepUnpackManaged {
     tar_gz_path: Utf8PathBuf,
     target_dir: Utf8PathBuf,
+    version_number: usize,
+    version_label: String,
+    session: String,
     size_limit: u64,
 }
 
@@ -51,9 +66,123 @@ impl SetupStep for StepUnpackManaged {
     }
 
     fn setup(&self) -> Result<(), api::Error> {
-        unpack_into(&self.tar_gz_path, &self.target_dir, self.size_limit)
-            .map_err(|err| api::Error::new("Failed to unpack managed robot archive".into(), err))
+        let version = ManagedRobotVersion {
+            version_number: self.version_number,
+            version_label: self.version_label.clone(),
+            session: self.session.clone(),
+            archive_hash: hash_file(&self.tar_gz_path).map_err(|err| {
+                api::Error::new(
+                    "Failed to compute hash of managed robot archive".into(),
+                    err,
+                )
+            })?,
+        };
+        update_managed_robot(
+            &self.tar_gz_path,
+            &self.target_dir,
+            &version,
+            self.size_limit,
+        )
+        .map_err(|err| api::Error::new("Failed to unpack managed robot archive".into(), err))
+    }
+}
+
+// Access to the managed robot is only granted on the staging directory, which becomes the target
+// when it is swapped in. Hence, the session is part of the version: if it changes, the robot is
+// unpacked again, such that the new session obtains access.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct ManagedRobotVersion {
+    version_number: usize,
+    version_label: String,
+    session: String,
+    archive_hash: String,
+}
+
+fn update_managed_robot(
+    tar_gz_path: &Utf8Path,
+    target_dir: &Utf8Path,
+    version: &ManagedRobotVersion,
+    size_limit: u64,
+) -> anyhow::Result<()> {
+    let staging_dir = managed_staging_directory(target_dir);
+    if target_dir.is_dir() && read_version_record(target_dir).as_ref() == Some(version) {
+        info!("Managed robot in {target_dir} is up to date, skipping extraction");
+        if staging_dir.exists() {
+            remove_dir_all(&staging_dir)?;
+        }
+        return Ok(());
+    }
+    clear_directory(&staging_dir)?;
+    unpack_into(tar_gz_path, &staging_dir, size_limit)?;
+    swap_in(target_dir, version)
+}
+
+fn read_version_record(target_dir: &Utf8Path) -> Option<ManagedRobotVersion> {
+    serde_json::from_str(&read_to_string(managed_version_record(target_dir)).ok()?).ok()
+}
+
+fn clear_directory(directory: &Utf8Path) -> anyhow::Result<()> {
+    for entry in top_level_directory_entries(directory)? {
+        if entry.is_dir() {
+            remove_dir_all(entry)?
+        } else {
+            remove_file(entry)?
+        }
+    }
+    Ok(())
+}
+
+fn swap_in(target_dir: &Utf8Path, version: &ManagedRobotVersion) -> anyhow::Result<()> {
+    let staging_dir = managed_staging_directory(target_dir);
+    let previous_dir = managed_previous_directory(target_dir);
+    let version_record = managed_version_record(target_dir);
+    let previous_version_record = managed_previous_version_record(target_dir);
+
+    if previous_dir.exists() {
+        remove_dir_all(&previous_dir)?;
+    }
+    if previous_version_record.exists() {
+        remove_file(&previous_version_record)?;
+    }
+    if target_dir.exists() {
+        rename(target_dir, &previous_dir)
+            .context(format!("Failed to move {target_dir} to {previous_dir}"))?;
+        if version_record.exists() {
+            rename(&version_record, &previous_version_record).context(format!(
+                "Failed to move {version_record} to {previous_version_record}"
+            ))?;
+        }
+    }
+    if let Err(error) = rename(&staging_dir, target_dir) {
+        if previous_dir.exists() {
+            rename(&previous_dir, target_dir).context(format!(
+                "Failed to roll back {target_dir} to previous version"
+            ))?;
+            if previous_version_record.exists() {
+                rename(&previous_version_record, &version_record).context(format!(
+                    "Failed to roll back {version_record} to previous version"
+                ))?;
+            }
+        }
+        return Err(error).context(format!("Failed to move {staging_dir} to {target_dir}"));
     }
+    write(&version_record, serde_json::to_string(version)?)
+        .context(format!("Failed to write {version_record}"))?;
+    info!(
+        "Managed robot in {target_dir} updated to version {}",
+        version.version_number
+    );
+    Ok(())
+}
+
+fn hash_file(path: &Utf8Path) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(
+        &mut File::open(path).context(format!("Failed to open {path}"))?,
+        &mut hasher,
+    )
+    .context(format!("Failed to read {path}"))?;
+    Ok(format!("{:x}", hasher.finalize()))
 }
 
 fn unpack_into(
@@ -102,7 +231,7 @@ mod tests {
     use flate2::Compression;
     use flate2::write::GzEncoder;
     use std::fs;
-    use std::io::{self, Write};
+    use std::io::Write;
     use tempfile::tempdir;
 
     #[test]
@@ -144,6 +273,127 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn update_managed_robot_keeps_previous_version() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let temp_dir_path = Utf8PathBuf::try_from(temp_dir.path().to_path_buf())?;
+        let target_dir = temp_dir_path.join("managed").join("plan");
+        fs::create_dir_all(managed_staging_directory(&target_dir))?;
+
+        let dir_to_be_archived = temp_dir_path.join("archive");
+        fs::create_dir(&dir_to_be_archived)?;
+        fs::write(dir_to_be_archived.join("file.txt"), "v1")?;
+        let archive_path = temp_dir_path.join("archive.tar.gz");
+        archive_directory(&dir_to_be_archived, &archive_path, "robot")?;
+        let version_1 = ManagedRobotVersion {
+            version_number: 1,
+            version_label: "".into(),
+            session: "current_user".into(),
+            archive_hash: hash_file(&archive_path)?,
+        };
+        update_managed_robot(&archive_path, &target_dir, &version_1, 1024)?;
+
+        fs::create_dir_all(managed_staging_directory(&target_dir))?;
+        fs::write(dir_to_be_archived.join("file.txt"), "v2")?;
+        archive_directory(&dir_to_be_archived, &archive_path, "robot")?;
+        let version_2 = ManagedRobotVersion {
+            version_number: 2,
+            version_label: "".into(),
+            session: "current_user".into(),
+            archive_hash: hash_file(&archive_path)?,
+        };
+        update_managed_robot(&archive_path, &target_dir, &version_2, 1024)?;
+
+        assert_eq!(
+            fs::read_to_string(target_dir.join("robot").join("file.txt"))?,
+            "v2"
+        );
+        assert_eq!(
+            fs::read_to_string(
+                managed_previous_directory(&target_dir)
+                    .join("robot")
+                    .join("file.txt")
+            )?,
+            "v1"
+        );
+        assert_eq!(read_version_record(&target_dir), Some(version_2));
+        assert!(!managed_staging_directory(&target_dir).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn update_managed_robot_skips_unchanged_version() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let temp_dir_path = Utf8PathBuf::try_from(temp_dir.path().to_path_buf())?;
+        let target_dir = temp_dir_path.join("managed").join("plan");
+        fs::create_dir_all(managed_staging_directory(&target_dir))?;
+
+        let dir_to_be_archived = temp_dir_path.join("archive");
+        fs::create_dir(&dir_to_be_archived)?;
+        fs::write(dir_to_be_archived.join("file.txt"), "v1")?;
+        let archive_path = temp_dir_path.join("archive.tar.gz");
+        archive_directory(&dir_to_be_archived, &archive_path, "robot")?;
+        let version = ManagedRobotVersion {
+            version_number: 1,
+            version_label: "".into(),
+            session: "current_user".into(),
+            archive_hash: hash_file(&archive_path)?,
+        };
+        update_managed_robot(&archive_path, &target_dir, &version, 1024)?;
+        fs::write(target_dir.join("marker"), "")?;
+
+        fs::create_dir_all(managed_staging_directory(&target_dir))?;
+        update_managed_robot(&archive_path, &target_dir, &version, 1024)?;
+
+        assert!(target_dir.join("marker").is_file());
+        assert!(!managed_previous_directory(&target_dir).exists());
+        assert!(!managed_staging_directory(&target_dir).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn update_managed_robot_unpacks_again_for_new_session() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let temp_dir_path = Utf8PathBuf::try_from(temp_dir.path().to_path_buf())?;
+        let target_dir = temp_dir_path.join("managed").join("plan");
+        fs::create_dir_all(managed_staging_directory(&target_dir))?;
+
+        let dir_to_be_archived = temp_dir_path.join("archive");
+        fs::create_dir(&dir_to_be_archived)?;
+        fs::write(dir_to_be_archived.join("file.txt"), "v1")?;
+        let archive_path = temp_dir_path.join("archive.tar.gz");
+        archive_directory(&dir_to_be_archived, &archive_path, "robot")?;
+        let version_for_session = |session: &str| -> anyhow::Result<ManagedRobotVersion> {
+            Ok(ManagedRobotVersion {
+                version_number: 1,
+                version_label: "".into(),
+                session: session.into(),
+                archive_hash: hash_file(&archive_path)?,
+            })
+        };
+        update_managed_robot(
+            &archive_path,
+            &target_dir,
+            &version_for_session("current_user")?,
+            1024,
+        )?;
+
+        fs::create_dir_all(managed_staging_directory(&target_dir))?;
+        update_managed_robot(
+            &archive_path,
+            &target_dir,
+            &version_for_session("user_alice")?,
+            1024,
+        )?;
+
+        assert!(managed_previous_directory(&target_dir).is_dir());
+        assert_eq!(
+            read_version_record(&target_dir),
+            Some(version_for_session("user_alice")?)
+        );
+        Ok(())
+    }
+
     fn archive_directory(
         dir_to_be_archived: &Utf8Path,
         archive_path: &Utf8Path,
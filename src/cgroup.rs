@@ -0,0 +1,269 @@
+use crate::results::ResourceUsage;
+
+use anyhow::{Context, Result as AnyhowResult, bail};
+use camino::Utf8PathBuf;
+use log::{debug, error, info, warn};
+use std::ffi::CString;
+use std::fs::{create_dir, read_dir, read_to_string, remove_dir, write};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::sleep;
+use std::time::Duration;
+
+// Each supervised command is placed in its own cgroup below this directory, such that its entire
+// process tree can be killed atomically, including daemonized descendants which were reparented to
+// init. As long as it is not initialized, process trees are tracked via their parent PIDs instead.
+static BASE_DIRECTORY: OnceLock<Utf8PathBuf> = OnceLock::new();
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+pub fn init() {
+    match setup_base_directory() {
+        Ok(base_directory) => {
+            info!("Containing supervised processes in cgroups below {base_directory}");
+            let _ = BASE_DIRECTORY.set(base_directory);
+        }
+        Err(error) => info!(
+            "cgroup v2 containment unavailable, falling back to process tree tracking: {error:?}"
+        ),
+    }
+}
+
+fn setup_base_directory() -> AnyhowResult<Utf8PathBuf> {
+    let base_directory = own_cgroup()?.join("robotmk");
+    if base_directory.exists() {
+        // Left behind by a previous scheduler run which did not shut down cleanly.
+        for entry in
+            read_dir(&base_directory).context(format!("Failed to read {base_directory}"))?
+        {
+            let path = Utf8PathBuf::try_from(entry?.path())?;
+            if path.is_dir() {
+                warn!("Removing cgroup {path} left behind by previous scheduler run");
+                drop(Cgroup { path });
+            }
+        }
+    } else {
+        create_dir(&base_directory).context(format!("Failed to create {base_directory}"))?;
+    }
+    if !base_directory.join("cgroup.kill").exists() {
+        bail!("cgroup.kill is not supported, Linux 5.14 or newer is required");
+    }
+    // Memory accounting requires the memory controller, which is not necessarily delegated to us.
+    if let Err(error) = write(base_directory.join("cgroup.subtree_control"), "+memory") {
+        debug!("Failed to enable memory controller, peak memory will not be reported: {error}");
+    }
+    Ok(base_directory)
+}
+
+fn own_cgroup() -> AnyhowResult<Utf8PathBuf> {
+    cgroup_directory(
+        &read_to_string("/proc/self/mountinfo").context("Failed to read /proc/self/mountinfo")?,
+        &read_to_string("/proc/self/cgroup").context("Failed to read /proc/self/cgroup")?,
+    )
+}
+
+// Locates the directory of a cgroup (given by the content of /proc/<pid>/cgroup) in the cgroup v2
+// hierarchy mounted according to the content of /proc/<pid>/mountinfo.
+fn cgroup_directory(mount_info: &str, cgroups: &str) -> AnyhowResult<Utf8PathBuf> {
+    let (mount_root, mount_point) = mount_info
+        .lines()
+        .find_map(|line| {
+            let (mount, filesystem) = line.split_once(" - ")?;
+            if !filesystem.starts_with("cgroup2 ") {
+                return None;
+            }
+            let mut fields = mount.split(' ').skip(3);
+            Some((fields.next()?, fields.next()?))
+        })
+        .context("No cgroup v2 hierarchy mounted")?;
+    let own_path = cgroups
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .context("Not a member of the cgroup v2 hierarchy")?;
+    let relative_path = own_path.strip_prefix(mount_root).context(format!(
+        "cgroup {own_path} is not visible below {mount_point}"
+    ))?;
+    Ok(Utf8PathBuf::from(mount_point).join(relative_path.trim_start_matches('/')))
+}
+
+// Removed once dropped. Processes which are still alive at this point are killed, a contained
+// command must not leave anything behind.
+pub struct Cgroup {
+    path: Utf8PathBuf,
+}
+
+impl Cgroup {
+    pub fn create() -> Option<Self> {
+        let path = BASE_DIRECTORY.get()?.join(format!(
+            "command_{}",
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        match create_dir(&path) {
+            Ok(()) => Some(Self { path }),
+            Err(error) => {
+                error!(
+                    "Failed to create cgroup {path}, process tree will not be contained: {error}"
+                );
+                None
+            }
+        }
+    }
+
+    // Moves the calling process into the cgroup. Meant to be run between fork and exec, hence we
+    // avoid allocating. If joining fails, the process runs uncontained rather than not at all, see
+    // `contains`.
+    pub fn join_hook(
+        &self,
+    ) -> AnyhowResult<impl FnMut() -> std::io::Result<()> + Send + Sync + 'static> {
+        let procs_file = CString::new(self.path.join("cgroup.procs").into_string())
+            .context("Invalid cgroup path")?;
+        Ok(move || {
+            let fd = unsafe { libc::open(procs_file.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) };
+            if fd >= 0 {
+                unsafe {
+                    libc::write(fd, b"0".as_ptr().cast(), 1);
+                    libc::close(fd);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    pub fn contains(&self, pid: u32) -> bool {
+        read_to_string(self.path.join("cgroup.procs")).is_ok_and(|procs| {
+            procs
+                .lines()
+                .any(|member| member.parse::<u32>().is_ok_and(|member| member == pid))
+        })
+    }
+
+    pub fn kill(&self) {
+        if let Err(error) = write(self.path.join("cgroup.kill"), "1") {
+            error!("Failed to kill cgroup {}: {error}", self.path);
+        }
+    }
+
+    pub fn resource_usage(&self) -> Option<ResourceUsage> {
+        match self.read_resource_usage() {
+            Ok(resource_usage) => Some(resource_usage),
+            Err(error) => {
+                error!("{error:?}");
+                None
+            }
+        }
+    }
+
+    fn read_resource_usage(&self) -> AnyhowResult<ResourceUsage> {
+        let cpu_stat_file = self.path.join("cpu.stat");
+        let cpu_stat =
+            read_to_string(&cpu_stat_file).context(format!("Failed to read {cpu_stat_file}"))?;
+        let cpu_seconds = |key: &str| -> AnyhowResult<f64> {
+            let microseconds: u64 = cpu_stat
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+                .context(format!("{key} missing in {cpu_stat_file}"))?
+                .parse()
+                .context(format!("Failed to parse {key} in {cpu_stat_file}"))?;
+            Ok(microseconds as f64 / 1_000_000.0)
+        };
+        Ok(ResourceUsage {
+            user_cpu_seconds: cpu_seconds("user_usec")?,
+            system_cpu_seconds: cpu_seconds("system_usec")?,
+            peak_memory_bytes: read_to_string(self.path.join("memory.peak"))
+                .ok()
+                .and_then(|peak| peak.trim().parse().ok()),
+        })
+    }
+
+    fn process_count(&self) -> usize {
+        read_to_string(self.path.join("cgroup.procs"))
+            .map(|procs| procs.lines().count())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        let process_count = self.process_count();
+        if process_count > 0 {
+            warn!(
+                "Killing {process_count} process(es) left behind in cgroup {}",
+                self.path
+            );
+            self.kill();
+        }
+        // Killing is asynchronous, the cgroup cannot be removed until all members have exited.
+        for _ in 0..100 {
+            if remove_dir(&self.path).is_ok() {
+                return;
+            }
+            sleep(Duration::from_millis(10));
+        }
+        error!("Failed to remove cgroup {}", self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    const MOUNT_INFO: &str = "\
+24 30 0:22 / /sys rw,nosuid,nodev,noexec,relatime shared:7 - sysfs sysfs rw
+28 24 0:25 / /sys/fs/cgroup rw,nosuid,nodev,noexec,relatime shared:9 - cgroup2 cgroup2 rw,nsdelegate
+";
+
+    #[test]
+    fn cgroup_directory_below_mount_point() {
+        assert_eq!(
+            cgroup_directory(MOUNT_INFO, "0::/system.slice/robotmk.service\n").unwrap(),
+            "/sys/fs/cgroup/system.slice/robotmk.service"
+        );
+    }
+
+    #[test]
+    fn cgroup_directory_with_nested_mount_root() {
+        assert_eq!(
+            cgroup_directory(
+                "35 30 0:30 /system.slice /sys/fs/cgroup rw - cgroup2 cgroup2 rw\n",
+                "0::/system.slice/robotmk.service\n"
+            )
+            .unwrap(),
+            "/sys/fs/cgroup/robotmk.service"
+        );
+    }
+
+    #[test]
+    fn cgroup_directory_requires_cgroup_v2() {
+        assert!(cgroup_directory(MOUNT_INFO, "1:name=systemd:/init.scope\n").is_err());
+        assert!(
+            cgroup_directory(
+                "24 30 0:22 / /sys rw - sysfs sysfs rw\n",
+                "0::/system.slice/robotmk.service\n"
+            )
+            .is_err()
+        );
+    }
+
+    // Requires write access to a cgroup v2 hierarchy
+    #[ignore]
+    #[test]
+    fn kill_contains_daemonized_descendants() {
+        init();
+        let cgroup = Cgroup::create().unwrap();
+        let mut command = Command::new("sh");
+        command.args(["-c", "setsid sleep 30 & wait"]);
+        unsafe { command.pre_exec(cgroup.join_hook().unwrap()) };
+        let mut child = command.spawn().unwrap();
+        sleep(Duration::from_millis(200));
+        assert_eq!(cgroup.process_count(), 2);
+
+        cgroup.kill();
+
+        assert!(!child.wait().unwrap().success());
+        assert!(cgroup.resource_usage().is_some());
+        let path = cgroup.path.clone();
+        drop(cgroup);
+        assert!(!path.exists());
+    }
+}
@@ -1,9 +1,14 @@
+#[cfg(target_os = "linux")]
+use crate::cgroup::Cgroup;
 use crate::command_spec::CommandSpec;
+use crate::config::{TerminationLadder, TerminationSignal};
+use crate::process_registry;
+use crate::results::ResourceUsage;
 use crate::termination::{Outcome, kill_process_tree, waited};
 
 use anyhow::{Context, Result as AnyhowResult};
 use camino::Utf8PathBuf;
-use log::debug;
+use log::{debug, warn};
 use std::process::{ExitStatus, Stdio};
 use std::time::Duration;
 use sysinfo::Pid;
@@ -15,6 +20,7 @@ pub struct ChildProcessSupervisor<'a> {
     pub stdio_paths: Option<StdioPaths>,
     pub timeout: u64,
     pub cancellation_token: &'a CancellationToken,
+    pub termination_ladder: &'a TerminationLadder,
 }
 
 pub struct StdioPaths {
@@ -22,9 +28,18 @@ pub struct StdioPaths {
     pub stderr: Utf8PathBuf,
 }
 
+#[derive(Default)]
+pub struct ProcessReport {
+    // The stage of the termination ladder which actually ended the process, if it had to be stopped
+    pub terminated_by: Option<TerminationSignal>,
+    pub resource_usage: Option<ResourceUsage>,
+}
+
 impl ChildProcessSupervisor<'_> {
-    pub fn run(&self) -> AnyhowResult<Outcome<ExitStatus>> {
+    pub fn run(&self) -> AnyhowResult<(Outcome<ExitStatus>, ProcessReport)> {
         let mut command: Command = self.build_command()?;
+        let containment = Containment::new();
+        containment.prepare(&mut command)?;
 
         let (stdout_path, stderr_path) = if let Some(stdio_paths) = &self.stdio_paths {
             (
@@ -39,11 +54,21 @@ impl ChildProcessSupervisor<'_> {
             self.command_spec,
         );
 
-        wait_for_child(
+        let (outcome, terminated_by) = wait_for_child(
             Duration::from_secs(self.timeout),
             self.cancellation_token,
+            self.termination_ladder,
             &mut command,
-        )
+            &self.command_spec.to_string(),
+            &containment,
+        )?;
+        Ok((
+            outcome,
+            ProcessReport {
+                terminated_by,
+                resource_usage: containment.resource_usage(),
+            },
+        ))
     }
 
     fn build_command(&self) -> AnyhowResult<Command> {
@@ -67,57 +92,133 @@ impl ChildProcessSupervisor<'_> {
     }
 }
 
+// On Linux, the process tree is contained in a cgroup if available, such that it can be killed
+// atomically. Otherwise, we fall back to tracking the tree via parent PIDs, which misses
+// descendants that were reparented to init.
+struct Containment {
+    #[cfg(target_os = "linux")]
+    cgroup: Option<Cgroup>,
+}
+
+impl Containment {
+    fn new() -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            cgroup: Cgroup::create(),
+        }
+    }
+
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    fn prepare(&self, command: &mut Command) -> AnyhowResult<()> {
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = &self.cgroup {
+            let join_hook = cgroup.join_hook()?;
+            unsafe { command.pre_exec(join_hook) };
+        }
+        Ok(())
+    }
+
+    // A child which failed to join its cgroup is killed via its process tree.
+    fn kill(&self, child: &tokio::process::Child) {
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = &self.cgroup
+            && child.id().is_some_and(|pid| cgroup.contains(pid))
+        {
+            cgroup.kill();
+            return;
+        }
+        if let Some(id) = child.id() {
+            kill_process_tree(&Pid::from_u32(id))
+        }
+    }
+
+    fn resource_usage(&self) -> Option<ResourceUsage> {
+        #[cfg(target_os = "linux")]
+        return self.cgroup.as_ref().and_then(Cgroup::resource_usage);
+        #[cfg(not(target_os = "linux"))]
+        None
+    }
+}
+
 #[tokio::main]
 async fn wait_for_child(
     duration: Duration,
     flag: &CancellationToken,
+    termination_ladder: &TerminationLadder,
     command: &mut Command,
-) -> AnyhowResult<Outcome<ExitStatus>> {
+    command_description: &str,
+    containment: &Containment,
+) -> AnyhowResult<(Outcome<ExitStatus>, Option<TerminationSignal>)> {
     let child = &mut command.spawn().context("Failed to spawn subprocess")?;
-    match waited(duration, flag, child.wait()).await {
-        Outcome::Timeout => {
-            #[cfg(windows)]
-            kill_child_tree(child);
-            #[cfg(unix)]
-            interrupt_and_wait(child).await;
-            Ok(Outcome::Timeout)
-        }
-        Outcome::Cancel => {
-            #[cfg(windows)]
-            kill_child_tree(child);
-            #[cfg(unix)]
-            interrupt_and_wait(child).await;
-            Ok(Outcome::Cancel)
-        }
+    let _registration = child
+        .id()
+        .and_then(|pid| process_registry::register(pid, command_description));
+    let outcome = match waited(duration, flag, child.wait()).await {
+        Outcome::Timeout => Outcome::Timeout,
+        Outcome::Cancel => Outcome::Cancel,
         Outcome::Completed(result) => {
             if result.is_err() {
-                kill_child_tree(child);
+                containment.kill(child);
             }
-            Ok(Outcome::Completed(
-                result.context("Failed to retrieve exit status of subprocess")?,
-            ))
+            return Ok((
+                Outcome::Completed(result.context("Failed to retrieve exit status of subprocess")?),
+                None,
+            ));
         }
-    }
+    };
+    #[cfg(windows)]
+    let terminated_by = {
+        // Windows has no signals to escalate through, the process tree is killed right away.
+        let _ = termination_ladder;
+        containment.kill(child);
+        TerminationSignal::Kill
+    };
+    #[cfg(unix)]
+    let terminated_by = terminate_and_wait(child, termination_ladder, containment).await;
+    warn!("Subprocess was terminated by {terminated_by:?}");
+    Ok((outcome, Some(terminated_by)))
 }
 
-fn kill_child_tree(child: &tokio::process::Child) {
-    if let Some(id) = child.id() {
-        kill_process_tree(&Pid::from_u32(id))
+// Walks through the termination ladder until the process exits. If it survives all steps, the
+// process tree is killed.
+#[cfg(unix)]
+async fn terminate_and_wait(
+    child: &mut tokio::process::Child,
+    termination_ladder: &TerminationLadder,
+    containment: &Containment,
+) -> TerminationSignal {
+    use tokio::time::sleep;
+
+    for step in &termination_ladder.0 {
+        signal_process_group(child, step.signal);
+        tokio::select! {
+            _ = child.wait() => { return step.signal },
+            _ = sleep(Duration::from_secs(step.grace_period)) => { },
+        };
     }
+    containment.kill(child);
+    let _ = child.wait().await;
+    TerminationSignal::Kill
 }
 
 #[cfg(unix)]
-async fn interrupt_and_wait(child: &mut tokio::process::Child) {
+fn signal_process_group(child: &tokio::process::Child, signal: TerminationSignal) {
     use log::error;
     use nix::sys::signal::{Signal, killpg};
     use nix::unistd::{Pid, getpgid};
-    use tokio::time::sleep;
 
     if let Some(pid) = child.id() {
         match getpgid(Some(Pid::from_raw(pid as i32))) {
             Ok(gid) => {
-                if let Err(error) = killpg(gid, Signal::SIGINT) {
-                    error!("Failed to interrupt process group. Error message:\n{error:?}");
+                if let Err(error) = killpg(
+                    gid,
+                    match signal {
+                        TerminationSignal::Interrupt => Signal::SIGINT,
+                        TerminationSignal::Terminate => Signal::SIGTERM,
+                        TerminationSignal::Kill => Signal::SIGKILL,
+                    },
+                ) {
+                    error!("Failed to signal process group. Error message:\n{error:?}");
                 }
             }
             Err(error) => {
@@ -128,11 +229,40 @@ async fn interrupt_and_wait(child: &mut tokio::process::Child) {
             }
         }
     }
-    tokio::select! {
-        _ = child.wait() => { },
-        _ = sleep(Duration::from_secs(10)) => {
-            kill_child_tree(child);
-            let _ = child.wait().await;
-        },
-    };
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use crate::config::TerminationStep;
+
+    #[test]
+    fn termination_ladder_escalates() {
+        let mut command_spec = CommandSpec::new("sh");
+        command_spec.add_arguments(["-c", "trap '' INT; sleep 30"]);
+        let (outcome, process_report) = ChildProcessSupervisor {
+            command_spec: &command_spec,
+            stdio_paths: None,
+            timeout: 1,
+            cancellation_token: &CancellationToken::new(),
+            termination_ladder: &TerminationLadder(vec![
+                TerminationStep {
+                    signal: TerminationSignal::Interrupt,
+                    grace_period: 1,
+                },
+                TerminationStep {
+                    signal: TerminationSignal::Terminate,
+                    grace_period: 5,
+                },
+            ]),
+        }
+        .run()
+        .unwrap();
+        assert!(matches!(outcome, Outcome::Timeout));
+        assert_eq!(
+            process_report.terminated_by,
+            Some(TerminationSignal::Terminate)
+        );
+    }
 }
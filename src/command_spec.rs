@@ -1,3 +1,4 @@
+use camino::Utf8PathBuf;
 use std::convert::From;
 use std::ffi::OsString;
 use std::fmt::{Display, Formatter, Result};
@@ -9,6 +10,7 @@ pub struct CommandSpec {
     pub arguments: Vec<String>,
     pub envs_rendered_plain: Vec<(String, String)>,
     pub envs_rendered_obfuscated: Vec<(String, String)>,
+    pub current_directory: Option<Utf8PathBuf>,
 }
 
 impl Display for CommandSpec {
@@ -44,6 +46,9 @@ impl From<&CommandSpec> for Command {
                 .chain(command_spec.envs_rendered_obfuscated.iter())
                 .map(|(k, v)| (OsString::from(&k), OsString::from(&v))),
         );
+        if let Some(current_directory) = &command_spec.current_directory {
+            command.current_dir(current_directory);
+        }
         command
     }
 }
@@ -61,6 +66,7 @@ impl CommandSpec {
             arguments: vec![],
             envs_rendered_plain: vec![],
             envs_rendered_obfuscated: vec![],
+            current_directory: None,
         }
     }
 
@@ -96,6 +102,14 @@ impl CommandSpec {
         self
     }
 
+    pub fn set_current_directory(
+        &mut self,
+        current_directory: impl Into<Utf8PathBuf>,
+    ) -> &mut Self {
+        self.current_directory = Some(current_directory.into());
+        self
+    }
+
     pub fn to_command_string(&self) -> String {
         let mut command = Command::new(self.executable.clone());
         command.args(&self.arguments);
@@ -119,6 +133,7 @@ mod tests {
             ],
             envs_rendered_plain: vec![("ROBOCORP_HOME".into(), "/opt/rc_home".into())],
             envs_rendered_obfuscated: vec![("RCC_REMOTE_ORIGIN".into(), "http://1.com".into())],
+            current_directory: None,
         };
         let expected = "ROBOCORP_HOME=\"/opt/rc_home\" RCC_REMOTE_ORIGIN=*** \"/my/binary\" \"mandatory\" \"--flag\" \"--option\" \"value\"";
         assert_eq!(format!("{command_spec}"), expected);
@@ -147,6 +162,7 @@ mod tests {
                 String::from("obfuscated_key"),
                 String::from("obfuscated_val"),
             )],
+            current_directory: Some("/my/cwd".into()),
         });
         assert_eq!(command.get_program(), expected.get_program());
         assert_eq!(
@@ -157,6 +173,10 @@ mod tests {
             command.get_envs().collect::<Vec<_>>(),
             expected.get_envs().collect::<Vec<_>>()
         );
+        assert_eq!(
+            command.get_current_dir(),
+            Some(std::path::Path::new("/my/cwd"))
+        );
     }
 
     #[test]
@@ -168,6 +188,7 @@ mod tests {
                 arguments: vec![],
                 envs_rendered_plain: vec![],
                 envs_rendered_obfuscated: vec![],
+                current_directory: None,
             }
         )
     }
@@ -179,6 +200,7 @@ mod tests {
             arguments: vec![],
             envs_rendered_plain: vec![],
             envs_rendered_obfuscated: vec![],
+            current_directory: None,
         };
         command_spec.add_argument("arg");
         assert_eq!(
@@ -188,6 +210,7 @@ mod tests {
                 arguments: vec!["arg".into()],
                 envs_rendered_plain: vec![],
                 envs_rendered_obfuscated: vec![],
+                current_directory: None,
             }
         );
     }
@@ -219,6 +242,7 @@ mod tests {
             arguments: vec![],
             envs_rendered_plain: vec![],
             envs_rendered_obfuscated: vec![],
+            current_directory: None,
         };
         command_spec.add_arguments(vec!["arg1", "arg2"]);
         assert_eq!(
@@ -228,6 +252,7 @@ mod tests {
                 arguments: vec!["arg1".into(), "arg2".into()],
                 envs_rendered_plain: vec![],
                 envs_rendered_obfuscated: vec![],
+                current_directory: None,
             }
         );
     }
@@ -1,12 +1,20 @@
 use crate::section::Host;
-use anyhow::Result as AnyhowResult;
+use anyhow::{Result as AnyhowResult, bail};
 use camino::{Utf8Path, Utf8PathBuf};
 use serde::{Deserialize, Serialize};
 use serde_json::from_str;
 use std::fs::read_to_string;
 
 pub fn load(path: &Utf8Path) -> AnyhowResult<Config> {
-    Ok(from_str(&read_to_string(path)?)?)
+    Ok(load_with_raw_content(path)?.0)
+}
+
+// The raw content identifies the exact configuration which was loaded, e.g. via its digest.
+pub fn load_with_raw_content(path: &Utf8Path) -> AnyhowResult<(Config, String)> {
+    let raw_content = read_to_string(path)?;
+    let config: Config = from_str(&raw_content)?;
+    config.cleanup_config.validate()?;
+    Ok((config, raw_content))
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -15,6 +23,58 @@ pub struct Config {
     pub rcc_config: RCCConfig,
     pub conda_config: CondaConfig,
     pub plan_groups: Vec<SequentialPlanGroup>,
+    #[serde(default)]
+    pub history_config: HistoryConfig,
+    #[serde(default)]
+    pub cleanup_config: CleanupConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HistoryConfig {
+    pub max_runs_per_plan: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_runs_per_plan: 1000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct CleanupConfig {
+    pub interval_secs: u64,
+    // Quota over the working directories of all plans. Once exceeded, the oldest runs are removed,
+    // regardless of the plan they belong to.
+    pub working_directory_quota: Option<u64>,
+    // Run directories older than this are replaced by compressed archives. The archives are
+    // subject to the same cleanup policies as the directories they replace.
+    pub archive_after_secs: Option<u64>,
+    // Applies to the stdio logs of environment building and RCC setup steps.
+    pub build_logs_max_age_secs: u64,
+}
+
+impl CleanupConfig {
+    // A zero interval would walk all run directories continuously.
+    fn validate(&self) -> AnyhowResult<()> {
+        if self.interval_secs == 0 {
+            bail!("Cleanup interval must be at least one second");
+        }
+        Ok(())
+    }
+}
+
+impl Default for CleanupConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 300,
+            working_directory_quota: None,
+            archive_after_secs: None,
+            build_logs_max_age_secs: 604800,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -46,6 +106,27 @@ pub struct CondaConfig {
 pub struct SequentialPlanGroup {
     pub plans: Vec<PlanConfig>,
     pub execution_interval: u64,
+    #[serde(default)]
+    pub missed_tick_policy: MissedTickPolicy,
+    #[serde(default)]
+    pub execution_mode: GroupExecutionMode,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum MissedTickPolicy {
+    #[default]
+    Burst,
+    Delay,
+    Skip,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum GroupExecutionMode {
+    #[default]
+    Sequential,
+    Parallel {
+        max_concurrency: usize,
+    },
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -71,6 +152,16 @@ pub struct PlanConfig {
     pub working_directory_cleanup_config: WorkingDirectoryCleanupConfig,
     pub host: Host,
     pub metadata: PlanMetadata,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub dry_run_validation: DryRunValidationConfig,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    // Applied to failed runs instead of the working directory cleanup config, such that they can
+    // be kept around longer for investigation.
+    #[serde(default)]
+    pub failed_run_cleanup_config: Option<WorkingDirectoryCleanupConfig>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -86,6 +177,20 @@ pub struct RobotConfig {
     pub argument_files: Vec<Utf8PathBuf>,
     pub exit_on_failure: bool,
     pub environment_variables_rendered_obfuscated: Vec<RobotFrameworkObfuscatedEnvVar>,
+    #[serde(default)]
+    pub execution_mode: RobotExecutionMode,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum RobotExecutionMode {
+    #[default]
+    Robot,
+    // Pabot runs in the output directory of the attempt, such that parallel runs do not share its
+    // `.pabotsuitenames`. Hence, `${EXECDIR}` and relative paths in robot arguments are resolved
+    // against the output directory, whereas robot mode keeps the scheduler's current directory.
+    Pabot {
+        processes: usize,
+    },
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -105,6 +210,46 @@ pub struct ExecutionConfig {
     pub n_attempts_max: usize,
     pub retry_strategy: RetryStrategy,
     pub timeout: u64,
+    #[serde(default)]
+    pub retry_policy: RetryPolicyConfig,
+    #[serde(default)]
+    pub total_budget: Option<TotalBudgetConfig>,
+    #[serde(default)]
+    pub termination_ladder: TerminationLadder,
+}
+
+// The signals sent to a process group which has to be stopped, each followed by a grace period
+// (in seconds). If the process is still alive after the last step, the process tree is killed.
+// Only relevant on Unix, on Windows, processes are always killed right away.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TerminationLadder(pub Vec<TerminationStep>);
+
+impl Default for TerminationLadder {
+    fn default() -> Self {
+        Self(vec![TerminationStep {
+            signal: TerminationSignal::Interrupt,
+            grace_period: 10,
+        }])
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TerminationStep {
+    pub signal: TerminationSignal,
+    pub grace_period: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum TerminationSignal {
+    Interrupt,
+    Terminate,
+    Kill,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TotalBudgetConfig {
+    pub budget: u64,
+    pub rebot_reserve: u64,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -113,6 +258,79 @@ pub enum RetryStrategy {
     Complete,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct RetryPolicyConfig {
+    pub retry_on: Vec<RetryTrigger>,
+    // Retries after test failures only re-run the failed tests with one of these tags, and only
+    // happen if there are such tests. This requires `RetryStrategy::Incremental`, since complete
+    // retries re-run all tests anyway.
+    pub retry_test_tags: Vec<String>,
+    pub delay: RetryDelay,
+}
+
+impl Default for RetryPolicyConfig {
+    fn default() -> Self {
+        Self {
+            retry_on: vec![
+                RetryTrigger::TestFailures,
+                RetryTrigger::RobotFailure,
+                RetryTrigger::EnvironmentFailure,
+                RetryTrigger::TimedOut,
+                RetryTrigger::OtherError,
+            ],
+            retry_test_tags: vec![],
+            delay: RetryDelay::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum RetryTrigger {
+    TestFailures,
+    RobotFailure,
+    EnvironmentFailure,
+    TimedOut,
+    OtherError,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum RetryDelay {
+    #[default]
+    None,
+    Constant {
+        seconds: u64,
+    },
+    Exponential {
+        initial_seconds: u64,
+        factor: u64,
+        max_seconds: u64,
+    },
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct HooksConfig {
+    pub pre_run: Vec<HookConfig>,
+    pub post_run: Vec<HookConfig>,
+    pub fail_run_on_pre_run_hook_failure: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HookConfig {
+    pub name: String,
+    pub command: Vec<String>,
+    pub timeout: u64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum DryRunValidationConfig {
+    #[default]
+    Disabled,
+    Enabled {
+        timeout: u64,
+    },
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum EnvironmentConfig {
     System,
@@ -174,6 +392,16 @@ pub struct UserSessionConfig {
 pub enum WorkingDirectoryCleanupConfig {
     MaxAgeSecs(u64),
     MaxExecutions(usize),
+    MaxTotalBytes(u64),
+    // Runs are removed as soon as any of the configured limits is exceeded.
+    Combined {
+        #[serde(default)]
+        max_age_secs: Option<u64>,
+        #[serde(default)]
+        max_executions: Option<usize>,
+        #[serde(default)]
+        max_total_bytes: Option<u64>,
+    },
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -0,0 +1,47 @@
+use crate::history::RunRecord;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+
+// The scheduler accepts one request per connection. Requests and responses are single lines of
+// JSON.
+
+// The socket lives in its own directory, which only the scheduler user can access. This way, the
+// socket is never reachable by others, not even between binding and restricting its permissions.
+#[cfg(unix)]
+pub fn control_socket_directory(runtime_directory: &Utf8Path) -> Utf8PathBuf {
+    runtime_directory.join("control")
+}
+
+#[cfg(unix)]
+pub fn control_socket_path(runtime_directory: &Utf8Path) -> Utf8PathBuf {
+    control_socket_directory(runtime_directory).join("control.sock")
+}
+
+#[cfg(windows)]
+pub fn control_socket_path(_runtime_directory: &Utf8Path) -> Utf8PathBuf {
+    Utf8PathBuf::from(r"\\.\pipe\robotmk_scheduler_control")
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ControlRequest {
+    Run { plan_id: String },
+    Pause { plan_id: String },
+    Resume { plan_id: String },
+    Status { plan_id: String },
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ControlResponse {
+    Accepted,
+    Status(PlanStatus),
+    Error(String),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PlanStatus {
+    pub plan_id: String,
+    pub paused: bool,
+    pub running: bool,
+    pub last_run: Option<RunRecord>,
+}
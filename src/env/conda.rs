@@ -1,7 +1,10 @@
 use super::ResultCode;
 use super::robotmk_env_manifest::parse_robotmk_environment_manifest;
 use crate::command_spec::CommandSpec;
-use crate::config::{CondaEnvironmentSource, HTTPProxyConfig, TlsCertificateValidation};
+use crate::config::{
+    CondaEnvironmentSource, HTTPProxyConfig, TerminationLadder, TerminationSignal,
+    TlsCertificateValidation,
+};
 use crate::results::BuildOutcome;
 use crate::session::{RunSpec, Session};
 use crate::termination::{Cancelled, Outcome};
@@ -27,6 +30,7 @@ pub struct CondaEnvironment {
     pub tls_certificate_validation: TlsCertificateValidation,
     pub tls_revokation_enabled: bool,
     pub build_timeout: u64,
+    pub termination_ladder: TerminationLadder,
     pub build_runtime_directory: Utf8PathBuf,
 }
 
@@ -87,6 +91,7 @@ impl CondaEnvironment {
         for (key, value) in command_spec.envs_rendered_obfuscated {
             wrapped_spec.add_obfuscated_env(key, value);
         }
+        wrapped_spec.current_directory = command_spec.current_directory;
         wrapped_spec
     }
 
@@ -103,12 +108,12 @@ impl CondaEnvironment {
         build_step_label: &str,
         plan_id: &str,
     ) -> BuildStepOutcome {
-        match session.run(run_spec) {
-            Ok(Outcome::Completed(0)) => {
+        match session.run_with_report(run_spec) {
+            Ok((Outcome::Completed(0), _)) => {
                 info!("Plan {plan_id}: {build_step_label}: success");
                 BuildStepOutcome::Success
             }
-            Ok(Outcome::Completed(_exit_code)) => {
+            Ok((Outcome::Completed(_exit_code), _)) => {
                 error!(
                     "Plan {plan_id}: {build_step_label}: non-zero exit code, plan will be dropped"
                 );
@@ -117,11 +122,13 @@ impl CondaEnvironment {
                     stdio_location = run_spec.runtime_base_path
                 )))
             }
-            Ok(Outcome::Timeout) => {
+            Ok((Outcome::Timeout, process_report)) => {
                 error!("Plan {plan_id}: {build_step_label}: timeout, plan will be dropped");
-                BuildStepOutcome::Failure(BuildStepOutcomeFailure::Timeout)
+                BuildStepOutcome::Failure(BuildStepOutcomeFailure::Timeout(
+                    process_report.terminated_by,
+                ))
             }
-            Ok(Outcome::Cancel) => {
+            Ok((Outcome::Cancel, _)) => {
                 error!("Plan {plan_id}: {build_step_label}: cancelled");
                 BuildStepOutcome::Failure(BuildStepOutcomeFailure::Cancelled)
             }
@@ -155,6 +162,7 @@ impl CondaEnvironment {
                 runtime_base_path: &self.build_runtime_directory.join("create"),
                 timeout: self.build_timeout,
                 cancellation_token,
+                termination_ladder: &self.termination_ladder,
             },
             session,
             "Environment creation",
@@ -235,7 +243,7 @@ impl CondaEnvironment {
         let elapsed: u64 = (Utc::now() - start_time).num_seconds().try_into().unwrap();
         if elapsed >= self.build_timeout {
             error!("Environment import timed out, plan {id} will be dropped");
-            return BuildStepOutcome::Failure(BuildStepOutcomeFailure::Timeout);
+            return BuildStepOutcome::Failure(BuildStepOutcomeFailure::Timeout(None));
         };
 
         info!("Running conda-unpack for plan {id}");
@@ -246,6 +254,7 @@ impl CondaEnvironment {
                 runtime_base_path: &self.build_runtime_directory.join("conda-unpack"),
                 timeout: self.build_timeout - elapsed,
                 cancellation_token,
+                termination_ladder: &self.termination_ladder,
             },
             session,
             "conda-unpack",
@@ -319,7 +328,7 @@ impl CondaEnvironment {
             let elapsed: u64 = (Utc::now() - start_time).num_seconds().try_into().unwrap();
             if elapsed >= self.build_timeout {
                 error!("Timeout while running post-build commands, plan {plan_id} will be dropped");
-                return BuildStepOutcome::Failure(BuildStepOutcomeFailure::Timeout);
+                return BuildStepOutcome::Failure(BuildStepOutcomeFailure::Timeout(None));
             };
             info!("Running post-build command {command_name} for plan {plan_id}");
             if let BuildStepOutcome::Failure(failure) = Self::run_build_step(
@@ -331,6 +340,7 @@ impl CondaEnvironment {
                         .join(format!("post_build_{command_name}")),
                     timeout: self.build_timeout - elapsed,
                     cancellation_token,
+                    termination_ladder: &self.termination_ladder,
                 },
                 session,
                 &format!("Post-build command {command_name}"),
@@ -351,7 +361,7 @@ enum BuildStepOutcome {
 }
 
 enum BuildStepOutcomeFailure {
-    Timeout,
+    Timeout(Option<TerminationSignal>),
     Error(String),
     Cancelled,
 }
@@ -359,7 +369,9 @@ enum BuildStepOutcomeFailure {
 impl From<BuildStepOutcomeFailure> for Result<BuildOutcome, Cancelled> {
     fn from(failure: BuildStepOutcomeFailure) -> Self {
         match failure {
-            BuildStepOutcomeFailure::Timeout => Ok(BuildOutcome::Timeout),
+            BuildStepOutcomeFailure::Timeout(terminated_by) => {
+                Ok(BuildOutcome::Timeout { terminated_by })
+            }
             BuildStepOutcomeFailure::Error(error) => Ok(BuildOutcome::Error(error)),
             BuildStepOutcomeFailure::Cancelled => Err(Cancelled {}),
         }
@@ -407,6 +419,7 @@ mod tests {
                 tls_certificate_validation: TlsCertificateValidation::Enabled,
                 tls_revokation_enabled: false,
                 build_timeout: 600,
+                termination_ladder: TerminationLadder::default(),
                 build_runtime_directory: Utf8PathBuf::default(),
             }
             .wrap(to_be_wrapped.clone()),
@@ -426,6 +439,7 @@ mod tests {
             tls_certificate_validation: TlsCertificateValidation::Disabled,
             tls_revokation_enabled: false,
             build_timeout: 600,
+            termination_ladder: TerminationLadder::default(),
             build_runtime_directory: Utf8PathBuf::default(),
         }
         .make_create_command_spec("/env.yaml".into());
@@ -478,6 +492,7 @@ mod tests {
             tls_certificate_validation: TlsCertificateValidation::Enabled,
             tls_revokation_enabled: false,
             build_timeout: 600,
+            termination_ladder: TerminationLadder::default(),
             build_runtime_directory: Utf8PathBuf::default(),
         }
         .make_create_command_spec("/env.yaml".into());
@@ -526,6 +541,7 @@ mod tests {
             ),
             tls_revokation_enabled: true,
             build_timeout: 600,
+            termination_ladder: TerminationLadder::default(),
             build_runtime_directory: Utf8PathBuf::default(),
         }
         .make_create_command_spec("/env.yaml".into());
@@ -571,6 +587,7 @@ mod tests {
             tls_certificate_validation: TlsCertificateValidation::Enabled,
             tls_revokation_enabled: false,
             build_timeout: 600,
+            termination_ladder: TerminationLadder::default(),
             build_runtime_directory: Utf8PathBuf::default(),
         };
 
@@ -599,6 +616,7 @@ mod tests {
             tls_certificate_validation: TlsCertificateValidation::Enabled,
             tls_revokation_enabled: false,
             build_timeout: 600,
+            termination_ladder: TerminationLadder::default(),
             build_runtime_directory: Utf8PathBuf::default(),
         };
 
@@ -1,6 +1,7 @@
 use super::ResultCode;
+use crate::child_process_supervisor::ProcessReport;
 use crate::command_spec::CommandSpec;
-use crate::config::RCCEnvironmentConfig;
+use crate::config::{RCCEnvironmentConfig, TerminationLadder};
 use crate::results::BuildOutcome;
 use crate::session::{RunSpec, Session};
 use crate::termination::{Cancelled, Outcome};
@@ -20,6 +21,7 @@ pub struct RCCEnvironment {
     pub controller: String,
     pub space: String,
     pub build_timeout: u64,
+    pub termination_ladder: TerminationLadder,
     pub build_runtime_directory: Utf8PathBuf,
     pub robocorp_home: String,
 }
@@ -31,6 +33,7 @@ impl RCCEnvironment {
         plan_id: &str,
         rcc_binary_path: &Utf8Path,
         config: &RCCEnvironmentConfig,
+        termination_ladder: &TerminationLadder,
         build_runtime_directory: &Utf8Path,
     ) -> Self {
         Self {
@@ -41,6 +44,7 @@ impl RCCEnvironment {
             controller: "robotmk".into(),
             space: plan_id.into(),
             build_timeout: config.build_timeout,
+            termination_ladder: termination_ladder.clone(),
             build_runtime_directory: build_runtime_directory.into(),
             robocorp_home: robocorp_home.to_string(),
         }
@@ -68,21 +72,23 @@ impl RCCEnvironment {
                 self.build_timeout,
                 cancellation_token,
             ) {
-                Ok(Outcome::Completed(0)) => {
+                Ok((Outcome::Completed(0), _)) => {
                     info!("Environment import succeeded for plan {id}");
                 }
-                Ok(Outcome::Completed(_exit_code)) => {
+                Ok((Outcome::Completed(_exit_code), _)) => {
                     error!("Environment import not successful, plan {id} will be dropped");
                     return Ok(BuildOutcome::Error(format!(
                         "Environment import not successful, see {} for stdio logs",
                         self.build_runtime_directory
                     )));
                 }
-                Ok(Outcome::Timeout) => {
+                Ok((Outcome::Timeout, process_report)) => {
                     error!("Environment import timed out, plan {id} will be dropped");
-                    return Ok(BuildOutcome::Timeout);
+                    return Ok(BuildOutcome::Timeout {
+                        terminated_by: process_report.terminated_by,
+                    });
                 }
-                Ok(Outcome::Cancel) => {
+                Ok((Outcome::Cancel, _)) => {
                     error!("Environment import cancelled");
                     return Err(Cancelled {});
                 }
@@ -100,7 +106,9 @@ impl RCCEnvironment {
         let elapsed: u64 = (Utc::now() - start_time).num_seconds().try_into().unwrap();
         if elapsed >= self.build_timeout {
             error!("Environment import timed out, plan {id} will be dropped");
-            return Ok(BuildOutcome::Timeout);
+            return Ok(BuildOutcome::Timeout {
+                terminated_by: None,
+            });
         };
 
         match self.run_noop_command(
@@ -109,23 +117,25 @@ impl RCCEnvironment {
             self.build_timeout - elapsed,
             cancellation_token,
         ) {
-            Ok(Outcome::Completed(0)) => {
+            Ok((Outcome::Completed(0), _)) => {
                 info!("Environment building succeeded for plan {id}");
                 let duration = (Utc::now() - start_time).num_seconds();
                 Ok(BuildOutcome::Success(duration))
             }
-            Ok(Outcome::Completed(_exit_code)) => {
+            Ok((Outcome::Completed(_exit_code), _)) => {
                 error!("Environment building not successful, plan {id} will be dropped");
                 Ok(BuildOutcome::Error(format!(
                     "Environment building not successful, see {} for stdio logs",
                     self.build_runtime_directory
                 )))
             }
-            Ok(Outcome::Timeout) => {
+            Ok((Outcome::Timeout, process_report)) => {
                 error!("Environment building timed out, plan {id} will be dropped");
-                Ok(BuildOutcome::Timeout)
+                Ok(BuildOutcome::Timeout {
+                    terminated_by: process_report.terminated_by,
+                })
             }
-            Ok(Outcome::Cancel) => {
+            Ok((Outcome::Cancel, _)) => {
                 error!("Environment building cancelled");
                 Err(Cancelled {})
             }
@@ -158,6 +168,7 @@ impl RCCEnvironment {
         for (key, value) in command_spec.envs_rendered_obfuscated {
             wrapped_spec.add_obfuscated_env(key, value);
         }
+        wrapped_spec.current_directory = command_spec.current_directory;
         wrapped_spec
     }
 
@@ -176,19 +187,20 @@ impl RCCEnvironment {
         session: &Session,
         timeout: u64,
         cancellation_token: &CancellationToken,
-    ) -> anyhow::Result<Outcome<i32>> {
+    ) -> anyhow::Result<(Outcome<i32>, ProcessReport)> {
         let mut import_command_spec =
             Self::bundled_command_spec(&self.binary_path, self.robocorp_home.clone());
         import_command_spec
             .add_argument("holotree")
             .add_argument("import")
             .add_argument(catalog_zip);
-        session.run(&RunSpec {
+        session.run_with_report(&RunSpec {
             id: &format!("robotmk_env_import_{id}"),
             command_spec: &import_command_spec,
             runtime_base_path: &self.build_runtime_directory.join("import"),
             timeout,
             cancellation_token,
+            termination_ladder: &self.termination_ladder,
         })
     }
 
@@ -198,7 +210,7 @@ impl RCCEnvironment {
         session: &Session,
         timeout: u64,
         cancellation_token: &CancellationToken,
-    ) -> anyhow::Result<Outcome<i32>> {
+    ) -> anyhow::Result<(Outcome<i32>, ProcessReport)> {
         let mut noop_command_spec =
             Self::bundled_command_spec(&self.binary_path, self.robocorp_home.clone());
         noop_command_spec
@@ -215,12 +227,13 @@ impl RCCEnvironment {
             "cmd.exe",
         );
 
-        session.run(&RunSpec {
+        session.run_with_report(&RunSpec {
             id: &format!("robotmk_env_building_{id}"),
             command_spec: &noop_command_spec,
             runtime_base_path: &self.build_runtime_directory.join("build"),
             timeout,
             cancellation_token,
+            termination_ladder: &self.termination_ladder,
         })
     }
 
@@ -298,6 +311,7 @@ mod tests {
                 controller: "robotmk".into(),
                 space: "my_plan".into(),
                 build_timeout: 600,
+                termination_ladder: TerminationLadder::default(),
                 build_runtime_directory: Utf8PathBuf::default(),
                 robocorp_home: "~/.robocorp/".into(),
             }
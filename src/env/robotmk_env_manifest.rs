@@ -53,6 +53,7 @@ mod tests {
                 arguments: vec![],
                 envs_rendered_plain: vec![],
                 envs_rendered_obfuscated: vec![],
+                current_directory: None,
             }
         );
     }
@@ -71,6 +72,7 @@ mod tests {
                 arguments: vec!["--arg".into(), "value".into()],
                 envs_rendered_plain: vec![],
                 envs_rendered_obfuscated: vec![],
+                current_directory: None,
             }
         );
     }
@@ -0,0 +1,241 @@
+use crate::results::TestFlakiness;
+use crate::rf::output::{TestStatus, parse_test_results};
+
+use anyhow::{Context, Result as AnyhowResult};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, write};
+
+pub const FLAKINESS_HISTORY_LENGTH: usize = 20;
+
+pub fn flakiness_history_directory(state_directory: &Utf8Path) -> Utf8PathBuf {
+    state_directory.join("flakiness")
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct FlakinessHistory {
+    runs: Vec<RunRecord>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct RunRecord {
+    timestamp: i64,
+    tests: BTreeMap<String, bool>,
+}
+
+impl FlakinessHistory {
+    pub fn load(path: &Utf8Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match read_to_string(path)
+            .context(format!("Failed to read {path}"))
+            .and_then(|content| {
+                serde_json::from_str(&content).context(format!("Failed to parse {path}"))
+            }) {
+            Ok(history) => history,
+            Err(error) => {
+                warn!("{error:?}, starting with empty flakiness history");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn write(&self, path: &Utf8Path) -> AnyhowResult<()> {
+        write(path, serde_json::to_string(self)?).context(format!("Failed to write {path}"))
+    }
+
+    // Records for each test whether it needed retries to pass in this run. Only the most recent
+    // runs are kept.
+    pub fn record(&mut self, timestamp: i64, statuses_by_test: &BTreeMap<String, Vec<TestStatus>>) {
+        self.runs.push(RunRecord {
+            timestamp,
+            tests: statuses_by_test
+                .iter()
+                .map(|(test, statuses)| (test.clone(), needed_retries(statuses)))
+                .collect(),
+        });
+        if self.runs.len() > FLAKINESS_HISTORY_LENGTH {
+            self.runs
+                .drain(..self.runs.len() - FLAKINESS_HISTORY_LENGTH);
+        }
+    }
+
+    // The flakiness score of a test is the fraction of recorded runs in which it needed retries to
+    // pass. Tests which never needed retries are omitted.
+    pub fn report(&self) -> Vec<TestFlakiness> {
+        let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for run in &self.runs {
+            for (test, needed_retries) in &run.tests {
+                let (runs, runs_with_retries) = counts.entry(test).or_default();
+                *runs += 1;
+                if *needed_retries {
+                    *runs_with_retries += 1;
+                }
+            }
+        }
+        let latest_run = self.runs.last();
+        counts
+            .into_iter()
+            .filter(|(_, (_, runs_with_retries))| *runs_with_retries > 0)
+            .map(|(test, (runs, runs_with_retries))| TestFlakiness {
+                test: test.to_string(),
+                needed_retries_in_latest_run: latest_run
+                    .and_then(|run| run.tests.get(test))
+                    .copied()
+                    .unwrap_or(false),
+                runs,
+                runs_with_retries,
+                score: runs_with_retries as f64 / runs as f64,
+            })
+            .collect()
+    }
+}
+
+pub fn update_flakiness_history(
+    history_file: &Utf8Path,
+    timestamp: i64,
+    output_xml_files: &[Utf8PathBuf],
+) -> AnyhowResult<Vec<TestFlakiness>> {
+    let mut history = FlakinessHistory::load(history_file);
+    let statuses = statuses_by_test(output_xml_files);
+    if !statuses.is_empty() {
+        history.record(timestamp, &statuses);
+        history.write(history_file)?;
+    }
+    Ok(history.report())
+}
+
+// Collects the status of each test in each attempt it was part of, in the order of the attempts.
+// Attempts which were stopped may leave a truncated output file behind, such files are skipped.
+fn statuses_by_test(output_xml_files: &[Utf8PathBuf]) -> BTreeMap<String, Vec<TestStatus>> {
+    let mut statuses: BTreeMap<String, Vec<TestStatus>> = BTreeMap::new();
+    for output_xml_file in output_xml_files {
+        let test_results = match parse_test_results(output_xml_file) {
+            Ok(test_results) => test_results,
+            Err(error) => {
+                warn!("{error:?}, skipping attempt for flakiness tracking");
+                continue;
+            }
+        };
+        for test_result in test_results {
+            statuses
+                .entry(test_result.name)
+                .or_default()
+                .push(test_result.status);
+        }
+    }
+    statuses
+}
+
+fn needed_retries(statuses: &[TestStatus]) -> bool {
+    statuses
+        .iter()
+        .position(|status| *status == TestStatus::Fail)
+        .is_some_and(|first_failure| statuses[first_failure..].contains(&TestStatus::Pass))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needed_retries_after_failure() {
+        assert!(needed_retries(&[TestStatus::Fail, TestStatus::Pass]));
+        assert!(needed_retries(&[
+            TestStatus::Fail,
+            TestStatus::Fail,
+            TestStatus::Pass
+        ]));
+        assert!(!needed_retries(&[TestStatus::Pass]));
+        assert!(!needed_retries(&[TestStatus::Fail, TestStatus::Fail]));
+        assert!(!needed_retries(&[TestStatus::Pass, TestStatus::Fail]));
+    }
+
+    #[test]
+    fn history_report() {
+        let mut history = FlakinessHistory::default();
+        for (timestamp, division_statuses) in [
+            (1, vec![TestStatus::Fail, TestStatus::Pass]),
+            (2, vec![TestStatus::Pass]),
+            (3, vec![TestStatus::Pass]),
+            (4, vec![TestStatus::Fail, TestStatus::Pass]),
+        ] {
+            history.record(
+                timestamp,
+                &BTreeMap::from([
+                    ("Tasks.Addition".into(), vec![TestStatus::Pass]),
+                    ("Tasks.Division".into(), division_statuses),
+                ]),
+            );
+        }
+        assert_eq!(
+            history.report(),
+            [TestFlakiness {
+                test: "Tasks.Division".into(),
+                needed_retries_in_latest_run: true,
+                runs: 4,
+                runs_with_retries: 2,
+                score: 0.5,
+            }]
+        );
+    }
+
+    #[test]
+    fn history_is_rolling() {
+        let mut history = FlakinessHistory::default();
+        for timestamp in 0..(FLAKINESS_HISTORY_LENGTH as i64 + 5) {
+            history.record(
+                timestamp,
+                &BTreeMap::from([("Tasks.Addition".into(), vec![TestStatus::Pass])]),
+            );
+        }
+        assert_eq!(history.runs.len(), FLAKINESS_HISTORY_LENGTH);
+        assert_eq!(history.runs[0].timestamp, 5);
+    }
+
+    #[test]
+    fn truncated_output_is_skipped() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = Utf8Path::from_path(directory.path()).unwrap();
+        let output_xml = |status: &str| {
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<robot generator="Robot 7.0 (Python 3.12.0 on linux)" rpa="false" schemaversion="5">
+<suite id="s1" name="Tasks">
+<test id="s1-t1" name="Division">
+<status status="{status}" start="2024-01-01T00:00:00.000000" elapsed="0.001"/>
+</test>
+<status status="{status}" start="2024-01-01T00:00:00.000000" elapsed="0.001"/>
+</suite>
+</robot>
+"#
+            )
+        };
+        let first_attempt = directory.join("1.xml");
+        write(&first_attempt, output_xml("FAIL")).unwrap();
+        let second_attempt = directory.join("2.xml");
+        write(&second_attempt, &output_xml("PASS")[..200]).unwrap();
+        let third_attempt = directory.join("3.xml");
+        write(&third_attempt, output_xml("PASS")).unwrap();
+        let history_file = directory.join("history.json");
+
+        assert_eq!(
+            update_flakiness_history(
+                &history_file,
+                1,
+                &[first_attempt, second_attempt, third_attempt]
+            )
+            .unwrap(),
+            [TestFlakiness {
+                test: "Tasks.Division".into(),
+                needed_retries_in_latest_run: true,
+                runs: 1,
+                runs_with_retries: 1,
+                score: 1.0,
+            }]
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use crate::results::{AttemptOutcome, PlanExecutionReport, RebotOutcome};
+use crate::rf::output::{TestStatus, test_results_from_xml};
+
+use anyhow::{Context, Result as AnyhowResult};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::{OpenOptions, read_to_string, rename, write};
+use std::io::Write;
+
+pub fn run_history_directory(state_directory: &Utf8Path) -> Utf8PathBuf {
+    state_directory.join("history")
+}
+
+pub fn run_history_file(state_directory: &Utf8Path, plan_id: &str) -> Utf8PathBuf {
+    run_history_directory(state_directory).join(format!("{plan_id}.jsonl"))
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RunRecord {
+    pub timestamp: i64,
+    pub outcome: RunOutcome,
+    pub attempts: usize,
+    pub runtime: i64,
+    pub tests: TestCounts,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum RunOutcome {
+    AllTestsPassed,
+    TestFailures,
+    RobotFailure,
+    EnvironmentFailure,
+    TimedOut,
+    OtherError,
+    AbortedByPreRunHook,
+    DependencyFailed,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct TestCounts {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+// The outcome is taken from the merged rebot result if there is one, since the last attempt might
+// only have re-run a subset of the tests. The runtime is the sum of the attempt runtimes, it does
+// not include hooks, retry delays and rebot.
+impl From<&PlanExecutionReport> for RunRecord {
+    fn from(report: &PlanExecutionReport) -> Self {
+        let tests = match &report.rebot {
+            Some(RebotOutcome::Ok(rebot_result)) => test_counts(&rebot_result.xml),
+            _ => None,
+        };
+        Self {
+            timestamp: report.timestamp,
+            outcome: run_outcome(report, tests.as_ref()),
+            attempts: report.attempts.len(),
+            runtime: report.attempts.iter().map(|attempt| attempt.runtime).sum(),
+            tests: tests.unwrap_or_default(),
+        }
+    }
+}
+
+fn run_outcome(report: &PlanExecutionReport, tests: Option<&TestCounts>) -> RunOutcome {
+    match (
+        report.attempts.last().map(|attempt| &attempt.outcome),
+        tests,
+    ) {
+        _ if report.failed_dependency.is_some() => RunOutcome::DependencyFailed,
+        _ if report.aborted_by_pre_run_hook => RunOutcome::AbortedByPreRunHook,
+        (Some(AttemptOutcome::AllTestsPassed | AttemptOutcome::TestFailures), Some(tests)) => {
+            if tests.failed > 0 {
+                RunOutcome::TestFailures
+            } else {
+                RunOutcome::AllTestsPassed
+            }
+        }
+        (Some(AttemptOutcome::AllTestsPassed), None) => RunOutcome::AllTestsPassed,
+        (Some(AttemptOutcome::TestFailures), None) => RunOutcome::TestFailures,
+        (Some(AttemptOutcome::RobotFailure), _) => RunOutcome::RobotFailure,
+        (Some(AttemptOutcome::EnvironmentFailure), _) => RunOutcome::EnvironmentFailure,
+        (Some(AttemptOutcome::TimedOut), _) => RunOutcome::TimedOut,
+        (Some(AttemptOutcome::OtherError(_)) | None, _) => RunOutcome::OtherError,
+    }
+}
+
+fn test_counts(xml: &str) -> Option<TestCounts> {
+    let test_results = test_results_from_xml(xml)
+        .inspect_err(|error| warn!("Failed to count tests in rebot output: {error:?}"))
+        .ok()?;
+    let mut counts = TestCounts::default();
+    for test_result in test_results {
+        match test_result.status {
+            TestStatus::Pass => counts.passed += 1,
+            TestStatus::Fail => counts.failed += 1,
+            TestStatus::Skip | TestStatus::NotRun => counts.skipped += 1,
+        }
+    }
+    Some(counts)
+}
+
+// Records are appended one per line. Once the file holds more than `max_runs` records, it is
+// rewritten with only the most recent ones.
+pub fn append(history_file: &Utf8Path, record: &RunRecord, max_runs: usize) -> AnyhowResult<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_file)
+        .context(format!("Failed to open {history_file}"))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)
+        .context(format!("Failed to append to {history_file}"))?;
+    drop(file);
+
+    let records = read(history_file)?;
+    if records.len() > max_runs {
+        let temporary_file = Utf8PathBuf::from(format!("{history_file}.tmp"));
+        let mut content = String::new();
+        for record in &records[records.len() - max_runs..] {
+            content.push_str(&serde_json::to_string(record)?);
+            content.push('\n');
+        }
+        write(&temporary_file, content).context(format!("Failed to write {temporary_file}"))?;
+        rename(&temporary_file, history_file).context(format!(
+            "Failed to rename {temporary_file} to {history_file}"
+        ))?;
+    }
+    Ok(())
+}
+
+// Lines which cannot be parsed (e.g. a record which is being written concurrently) are skipped.
+pub fn read(history_file: &Utf8Path) -> AnyhowResult<Vec<RunRecord>> {
+    if !history_file.exists() {
+        return Ok(vec![]);
+    }
+    Ok(read_to_string(history_file)
+        .context(format!("Failed to read {history_file}"))?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+pub fn latest(history_file: &Utf8Path) -> AnyhowResult<Option<RunRecord>> {
+    Ok(read(history_file)?.pop())
+}
+
+// Marks a run directory with the outcome of the run, such that the cleanup can treat failed runs
+// differently. Runs which are still in progress have no marker.
+pub const RUN_OUTCOME_FILE_NAME: &str = "outcome.json";
+
+pub fn run_outcome_file(run_directory: &Utf8Path) -> Utf8PathBuf {
+    run_directory.join(RUN_OUTCOME_FILE_NAME)
+}
+
+pub fn write_run_outcome(run_directory: &Utf8Path, outcome: RunOutcome) -> AnyhowResult<()> {
+    let path = run_outcome_file(run_directory);
+    write(&path, serde_json::to_string(&outcome)?).context(format!("Failed to write {path}"))
+}
+
+pub fn read_run_outcome(run_directory: &Utf8Path) -> AnyhowResult<Option<RunOutcome>> {
+    let path = run_outcome_file(run_directory);
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(
+        serde_json::from_str(&read_to_string(&path).context(format!("Failed to read {path}"))?)
+            .context(format!("Failed to parse {path}"))?,
+    ))
+}
+
+// The fraction of runs since `since` (Unix timestamp) in which all tests passed. `None` if there
+// are no such runs.
+pub fn availability(records: &[RunRecord], since: i64) -> Option<f64> {
+    let recent: Vec<_> = records
+        .iter()
+        .filter(|record| record.timestamp >= since)
+        .collect();
+    if recent.is_empty() {
+        return None;
+    }
+    Some(
+        recent
+            .iter()
+            .filter(|record| record.outcome == RunOutcome::AllTestsPassed)
+            .count() as f64
+            / recent.len() as f64,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PlanMetadata;
+    use crate::results::{AttemptReport, AttemptsConfig, RebotResult};
+    use tempfile::tempdir;
+
+    fn record(timestamp: i64, outcome: RunOutcome) -> RunRecord {
+        RunRecord {
+            timestamp,
+            outcome,
+            attempts: 1,
+            runtime: 10,
+            tests: TestCounts::default(),
+        }
+    }
+
+    #[test]
+    fn append_keeps_most_recent_runs() {
+        let directory = tempdir().unwrap();
+        let history_file = Utf8PathBuf::try_from(directory.path().join("plan.jsonl")).unwrap();
+        for timestamp in 0..5 {
+            append(
+                &history_file,
+                &record(timestamp, RunOutcome::AllTestsPassed),
+                3,
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            read(&history_file)
+                .unwrap()
+                .iter()
+                .map(|record| record.timestamp)
+                .collect::<Vec<_>>(),
+            [2, 3, 4]
+        );
+    }
+
+    fn attempt(index: usize, outcome: AttemptOutcome) -> AttemptReport {
+        AttemptReport {
+            index,
+            outcome,
+            runtime: 5,
+            terminated_by: None,
+            resource_usage: None,
+        }
+    }
+
+    fn report(attempts: Vec<AttemptReport>, rebot_xml: Option<&str>) -> PlanExecutionReport {
+        PlanExecutionReport {
+            plan_id: "plan".into(),
+            timestamp: 10,
+            attempts,
+            rebot: rebot_xml.map(|xml| {
+                RebotOutcome::Ok(RebotResult {
+                    xml: xml.into(),
+                    html_base64: String::new(),
+                    timestamp: 20,
+                })
+            }),
+            config: AttemptsConfig {
+                interval: 60,
+                timeout: 30,
+                n_attempts_max: 2,
+                total_budget: None,
+            },
+            metadata: PlanMetadata {
+                application: "app".into(),
+                suite_name: "suite".into(),
+                variant: String::new(),
+            },
+            hooks: vec![],
+            aborted_by_pre_run_hook: false,
+            flakiness: vec![],
+            failed_dependency: None,
+        }
+    }
+
+    // The second attempt only re-ran the tagged test, which passed. The untagged test failed in the
+    // first attempt and is still failed in the merged result.
+    const MERGED_XML_WITH_UNTAGGED_FAILURE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<robot generator="Rebot 7.0 (Python 3.12.0 on linux)" rpa="false" schemaversion="5">
+<suite id="s1" name="Tasks">
+<test id="s1-t1" name="Tagged">
+<tag>retry</tag>
+<status status="PASS" start="2024-01-01T00:00:00.000000" elapsed="0.001"/>
+</test>
+<test id="s1-t2" name="Untagged">
+<status status="FAIL" start="2024-01-01T00:00:00.000000" elapsed="0.001"/>
+</test>
+<status status="FAIL" start="2024-01-01T00:00:00.000000" elapsed="0.002"/>
+</suite>
+</robot>
+"#;
+
+    #[test]
+    fn outcome_from_merged_result_after_passing_tagged_retry() {
+        let record = RunRecord::from(&report(
+            vec![
+                attempt(1, AttemptOutcome::TestFailures),
+                attempt(2, AttemptOutcome::AllTestsPassed),
+            ],
+            Some(MERGED_XML_WITH_UNTAGGED_FAILURE),
+        ));
+        assert_eq!(record.outcome, RunOutcome::TestFailures);
+        assert_eq!(
+            record.tests,
+            TestCounts {
+                passed: 1,
+                failed: 1,
+                skipped: 0
+            }
+        );
+        assert_eq!(record.runtime, 10);
+    }
+
+    #[test]
+    fn outcome_from_last_attempt_without_rebot_result() {
+        let record = RunRecord::from(&report(
+            vec![
+                attempt(1, AttemptOutcome::TestFailures),
+                attempt(2, AttemptOutcome::AllTestsPassed),
+            ],
+            None,
+        ));
+        assert_eq!(record.outcome, RunOutcome::AllTestsPassed);
+        assert_eq!(record.tests, TestCounts::default());
+    }
+
+    #[test]
+    fn availability_since() {
+        let records = [
+            record(10, RunOutcome::TestFailures),
+            record(20, RunOutcome::AllTestsPassed),
+            record(30, RunOutcome::TimedOut),
+            record(40, RunOutcome::AllTestsPassed),
+            record(50, RunOutcome::AllTestsPassed),
+        ];
+        assert_eq!(availability(&records, 25), Some(2.0 / 3.0));
+        assert_eq!(availability(&records, 60), None);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::command_spec::CommandSpec;
+use crate::config::{HookConfig, HooksConfig, TerminationLadder};
+use crate::env::{Environment, ResultCode};
+use crate::log_context::{self, Phase};
+use crate::results::{HookOutcome, HookReport, HookStage};
+use crate::session::{RunSpec, Session};
+use crate::termination::{Cancelled, Outcome};
+
+use camino::Utf8Path;
+use chrono::Utc;
+use log::{error, info};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Hooks {
+    pub pre_run: Vec<Hook>,
+    pub post_run: Vec<Hook>,
+    pub fail_run_on_pre_run_hook_failure: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hook {
+    pub name: String,
+    pub command_spec: CommandSpec,
+    pub timeout: u64,
+    pub termination_ladder: TerminationLadder,
+}
+
+impl Hooks {
+    // Hooks are stopped in the same way as the robot of their plan.
+    pub fn new(hooks_config: HooksConfig, termination_ladder: &TerminationLadder) -> Self {
+        Self {
+            pre_run: hooks_from_configs(hooks_config.pre_run, termination_ladder),
+            post_run: hooks_from_configs(hooks_config.post_run, termination_ladder),
+            fail_run_on_pre_run_hook_failure: hooks_config.fail_run_on_pre_run_hook_failure,
+        }
+    }
+}
+
+// Hooks with an empty command are rejected as invalid plan configuration before we get here.
+fn hooks_from_configs(
+    hook_configs: Vec<HookConfig>,
+    termination_ladder: &TerminationLadder,
+) -> Vec<Hook> {
+    hook_configs
+        .into_iter()
+        .filter_map(|hook_config| {
+            let (executable, arguments) = hook_config.command.split_first()?;
+            let mut command_spec = CommandSpec::new(executable);
+            command_spec.add_arguments(arguments);
+            Some(Hook {
+                name: hook_config.name,
+                command_spec,
+                timeout: hook_config.timeout,
+                termination_ladder: termination_ladder.clone(),
+            })
+        })
+        .collect()
+}
+
+impl HookStage {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::PreRun => "pre_run",
+            Self::PostRun => "post_run",
+        }
+    }
+}
+
+pub fn run_hooks(
+    hooks: &[Hook],
+    stage: HookStage,
+    plan_id: &str,
+    environment: &Environment,
+    session: &Session,
+    cancellation_token: &CancellationToken,
+    output_directory: &Utf8Path,
+) -> Result<Vec<HookReport>, Cancelled> {
+    let _log_context = log_context::scoped(|context| {
+        context.phase = Some(match stage {
+            HookStage::PreRun => Phase::PreRunHooks,
+            HookStage::PostRun => Phase::PostRunHooks,
+        })
+    });
+    let mut reports = vec![];
+    for (index, hook) in hooks.iter().enumerate() {
+        info!(
+            "Plan {plan_id}: running {} hook {}",
+            stage.label(),
+            hook.name
+        );
+        let start_time = Utc::now();
+        let outcome = hook.run(
+            &format!("robotmk_{}_hook_{plan_id}_{index}", stage.label()),
+            environment,
+            session,
+            cancellation_token,
+            &output_directory.join(format!("{}_hook_{index}", stage.label())),
+        )?;
+        if outcome != HookOutcome::Success {
+            error!(
+                "Plan {plan_id}: {} hook {} failed: {outcome:?}",
+                stage.label(),
+                hook.name
+            );
+        }
+        reports.push(HookReport {
+            name: hook.name.clone(),
+            stage,
+            outcome,
+            runtime: (Utc::now() - start_time).num_seconds(),
+        });
+    }
+    Ok(reports)
+}
+
+impl Hook {
+    fn run(
+        &self,
+        id: &str,
+        environment: &Environment,
+        session: &Session,
+        cancellation_token: &CancellationToken,
+        runtime_base_path: &Utf8Path,
+    ) -> Result<HookOutcome, Cancelled> {
+        let exit_code = match session.run(&RunSpec {
+            id,
+            command_spec: &environment.wrap(self.command_spec.clone()),
+            runtime_base_path,
+            timeout: self.timeout,
+            cancellation_token,
+            termination_ladder: &self.termination_ladder,
+        }) {
+            Ok(Outcome::Completed(exit_code)) => exit_code,
+            Ok(Outcome::Timeout) => return Ok(HookOutcome::TimedOut),
+            Ok(Outcome::Cancel) => return Err(Cancelled {}),
+            Err(error) => {
+                return Ok(HookOutcome::OtherError(format!(
+                    "{error:?}, see {runtime_base_path} for stdio logs"
+                )));
+            }
+        };
+        Ok(match environment.create_result_code(exit_code) {
+            ResultCode::Success => HookOutcome::Success,
+            ResultCode::WrappedCommandFailed => HookOutcome::Failure(exit_code),
+            ResultCode::EnvironmentFailed => HookOutcome::EnvironmentFailure,
+            ResultCode::Error(error_message) => HookOutcome::OtherError(format!(
+                "{error_message}, see {runtime_base_path} for stdio logs"
+            )),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks_from_config() {
+        let termination_ladder = TerminationLadder(vec![]);
+        let hooks = Hooks::new(
+            HooksConfig {
+                pre_run: vec![HookConfig {
+                    name: "reset".into(),
+                    command: vec!["reset_data".into(), "--all".into()],
+                    timeout: 10,
+                }],
+                post_run: vec![],
+                fail_run_on_pre_run_hook_failure: true,
+            },
+            &termination_ladder,
+        );
+        let mut expected_command_spec = CommandSpec::new("reset_data");
+        expected_command_spec.add_argument("--all");
+        assert_eq!(
+            hooks,
+            Hooks {
+                pre_run: vec![Hook {
+                    name: "reset".into(),
+                    command_spec: expected_command_spec,
+                    timeout: 10,
+                    termination_ladder,
+                }],
+                post_run: vec![],
+                fail_run_on_pre_run_hook_failure: true,
+            }
+        );
+    }
+}
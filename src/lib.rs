@@ -1,12 +1,21 @@
+#[cfg(target_os = "linux")]
+pub mod cgroup;
 pub mod child_process_supervisor;
 pub mod command_spec;
 pub mod config;
+pub mod control;
 pub mod env;
+pub mod flakiness;
 pub mod fs;
+pub mod history;
+pub mod hooks;
 pub mod lock;
+pub mod log_context;
 pub mod plans;
+pub mod process_registry;
 pub mod results;
 pub mod rf;
+pub mod run_archive;
 pub mod section;
 pub mod session;
 pub mod tasks;
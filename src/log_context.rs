@@ -0,0 +1,84 @@
+use camino::Utf8PathBuf;
+use serde::Serialize;
+use std::cell::RefCell;
+
+// Describes what the current thread is working on, such that log records can be attributed to
+// plans. Plans are built and run synchronously on their own threads, hence a thread-local context
+// suffices.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogContext {
+    pub plan_id: Option<String>,
+    pub attempt: Option<usize>,
+    pub phase: Option<Phase>,
+    pub session: Option<String>,
+    // Records are additionally written to this file, if set
+    pub log_file: Option<Utf8PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    EnvironmentBuilding,
+    PreRunHooks,
+    Attempt,
+    Rebot,
+    PostRunHooks,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<LogContext> = RefCell::default();
+}
+
+pub fn current() -> LogContext {
+    CONTEXT.with_borrow(Clone::clone)
+}
+
+// The previous context is restored once the guard is dropped.
+#[must_use]
+pub fn scoped(update: impl FnOnce(&mut LogContext)) -> ContextGuard {
+    let previous = CONTEXT.with_borrow_mut(|context| {
+        let previous = context.clone();
+        update(context);
+        previous
+    });
+    ContextGuard {
+        previous: Some(previous),
+    }
+}
+
+pub struct ContextGuard {
+    previous: Option<LogContext>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            CONTEXT.set(previous);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_are_restored() {
+        let _plan = scoped(|context| context.plan_id = Some("login".into()));
+        {
+            let _attempt = scoped(|context| {
+                context.attempt = Some(2);
+                context.phase = Some(Phase::Attempt);
+            });
+            assert_eq!(current().attempt, Some(2));
+            assert_eq!(current().plan_id.as_deref(), Some("login"));
+        }
+        assert_eq!(
+            current(),
+            LogContext {
+                plan_id: Some("login".into()),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -1,59 +1,114 @@
+use crate::child_process_supervisor::ProcessReport;
+use crate::config::{RetryDelay, RetryPolicyConfig, RetryTrigger, TotalBudgetConfig};
 use crate::env::{Environment, ResultCode};
-use crate::results::{AttemptOutcome, AttemptReport, RebotOutcome};
-use crate::rf::rebot::Rebot;
+use crate::log_context::{self, Phase};
+use crate::results::{AttemptOutcome, AttemptReport, PlanActivity, RebotOutcome};
+use crate::rf::output::{TestStatus, parse_test_results};
+use crate::rf::rebot::{REBOT_TIMEOUT, Rebot};
 use crate::rf::robot::{Attempt, Robot};
 use crate::session::{RunSpec, Session};
-use crate::termination::{Cancelled, Outcome};
+use crate::termination::{Cancelled, Outcome, waited};
 use anyhow::Context;
 use camino::{Utf8Path, Utf8PathBuf};
 use chrono::Utc;
-use log::{error, info};
+use log::{error, info, warn};
+use std::future::pending;
+use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
+pub struct PlanRunSpec<'a> {
+    pub id: &'a str,
+    pub robot: &'a Robot,
+    pub environment: &'a Environment,
+    pub session: &'a Session,
+    pub timeout: u64,
+    pub cancellation_token: &'a CancellationToken,
+    pub output_directory: &'a Utf8Path,
+    pub report_activity: &'a dyn Fn(PlanActivity),
+}
+
 pub fn run_attempts_with_rebot(
-    robot: &Robot,
-    id: &str,
-    environment: &Environment,
-    session: &Session,
-    timeout: u64,
-    cancellation_token: &CancellationToken,
-    output_directory: &Utf8Path,
+    spec: &PlanRunSpec,
 ) -> Result<(Vec<AttemptReport>, Option<RebotOutcome>), Cancelled> {
+    let &PlanRunSpec {
+        id,
+        robot,
+        environment,
+        session,
+        timeout,
+        cancellation_token,
+        output_directory,
+        report_activity,
+    } = spec;
+    let budget = Budget {
+        start: Instant::now(),
+        total_budget: robot.total_budget.as_ref(),
+    };
     let mut attempt_reports = vec![];
     let mut output_paths: Vec<Utf8PathBuf> = vec![];
+    let mut only_tagged_tests = false;
 
-    for attempt in robot.attempts(output_directory) {
-        info!("Plan {id}: running attempt {}", attempt.index);
+    for index in 1..(robot.n_attempts_max + 1) {
+        let attempt = robot.attempt(output_directory, index, only_tagged_tests);
+        let delay = retry_delay(&robot.retry_policy.delay, attempt.index);
+        if budget.is_exhausted_after(delay) {
+            info!(
+                "Plan {id}: total budget exhausted, skipping attempt {}",
+                attempt.index
+            );
+            break;
+        }
+        if !delay.is_zero() {
+            info!(
+                "Plan {id}: waiting {}s before attempt {}",
+                delay.as_secs(),
+                attempt.index
+            );
+            wait(delay, cancellation_token)?;
+        }
         let attempt_index = attempt.index;
+        let _log_context = log_context::scoped(|context| {
+            context.attempt = Some(attempt_index);
+            context.phase = Some(Phase::Attempt);
+        });
+        info!("Plan {id}: running attempt {attempt_index}");
         let starttime = Utc::now();
-        let (outcome, output_path) = run_attempt(
-            id,
-            environment,
-            session,
-            timeout,
-            attempt,
-            cancellation_token,
-            output_directory,
-        )?;
+        report_activity(PlanActivity::RunningAttempt {
+            index: attempt_index,
+            since: starttime.timestamp(),
+        });
+        let (outcome, output_path, process_report) =
+            run_attempt(spec, attempt, budget.attempt_timeout(timeout))?;
         let endtime = Utc::now();
-        let success = matches!(&outcome, &AttemptOutcome::AllTestsPassed);
+        let retry = should_retry(&robot.retry_policy, &outcome, output_path.as_deref());
+        if retry == Retry::No && !matches!(outcome, AttemptOutcome::AllTestsPassed) {
+            info!("Plan {id}: retry policy does not permit retrying attempt {attempt_index}");
+        }
         attempt_reports.push(AttemptReport {
             index: attempt_index,
             outcome,
             runtime: (endtime - starttime).num_seconds(),
+            terminated_by: process_report.terminated_by,
+            resource_usage: process_report.resource_usage,
         });
         if let Some(output_path) = output_path {
             output_paths.push(output_path);
         }
-        if success {
-            break;
+        match retry {
+            Retry::No => break,
+            Retry::AllFailedTests => only_tagged_tests = false,
+            Retry::TaggedFailedTests => only_tagged_tests = true,
         }
     }
 
     if output_paths.is_empty() {
         return Ok((attempt_reports, None));
     }
+    let _log_context = log_context::scoped(|context| context.phase = Some(Phase::Rebot));
     info!("Plan {id}: Running rebot");
+    report_activity(PlanActivity::RunningRebot {
+        since: Utc::now().timestamp(),
+    });
     let rebot = Rebot {
         plan_id: id,
         environment,
@@ -63,44 +118,187 @@ pub fn run_attempts_with_rebot(
         input_paths: &output_paths,
         path_xml: &output_directory.join("rebot.xml"),
         path_html: &output_directory.join("rebot.html"),
+        timeout: budget.rebot_timeout(),
+        termination_ladder: &robot.termination_ladder,
     }
     .rebot()?;
 
     Ok((attempt_reports, Some(rebot)))
 }
 
+// Enforces the optional total budget of a plan run. Attempts share the budget minus the rebot
+// reserve, rebot gets whatever is left, but at least its reserve.
+struct Budget<'a> {
+    start: Instant,
+    total_budget: Option<&'a TotalBudgetConfig>,
+}
+
+impl Budget<'_> {
+    fn remaining_for_attempts(&self) -> Option<Duration> {
+        self.total_budget.map(|total_budget| {
+            Duration::from_secs(
+                total_budget
+                    .budget
+                    .saturating_sub(total_budget.rebot_reserve),
+            )
+            .saturating_sub(self.start.elapsed())
+        })
+    }
+
+    fn is_exhausted_after(&self, delay: Duration) -> bool {
+        self.remaining_for_attempts()
+            .is_some_and(|remaining| remaining.saturating_sub(delay).as_secs() == 0)
+    }
+
+    // A timeout of zero would abort the attempt right away, so it gets at least one second.
+    fn attempt_timeout(&self, timeout: u64) -> u64 {
+        match self.remaining_for_attempts() {
+            Some(remaining) => timeout.min(remaining.as_secs()).max(1),
+            None => timeout,
+        }
+    }
+
+    fn rebot_timeout(&self) -> u64 {
+        match self.total_budget {
+            Some(total_budget) => Duration::from_secs(total_budget.budget)
+                .saturating_sub(self.start.elapsed())
+                .as_secs()
+                .max(total_budget.rebot_reserve),
+            None => REBOT_TIMEOUT,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Retry {
+    No,
+    AllFailedTests,
+    TaggedFailedTests,
+}
+
+fn should_retry(
+    retry_policy: &RetryPolicyConfig,
+    outcome: &AttemptOutcome,
+    output_path: Option<&Utf8Path>,
+) -> Retry {
+    let trigger = match outcome {
+        AttemptOutcome::AllTestsPassed => return Retry::No,
+        AttemptOutcome::TestFailures => RetryTrigger::TestFailures,
+        AttemptOutcome::RobotFailure => RetryTrigger::RobotFailure,
+        AttemptOutcome::EnvironmentFailure => RetryTrigger::EnvironmentFailure,
+        AttemptOutcome::TimedOut => RetryTrigger::TimedOut,
+        AttemptOutcome::OtherError(_) => RetryTrigger::OtherError,
+    };
+    if !retry_policy.retry_on.contains(&trigger) {
+        return Retry::No;
+    }
+    match (trigger, output_path) {
+        (RetryTrigger::TestFailures, Some(output_path))
+            if !retry_policy.retry_test_tags.is_empty() =>
+        {
+            retry_tagged_failed_tests(output_path, &retry_policy.retry_test_tags)
+        }
+        _ => Retry::AllFailedTests,
+    }
+}
+
+// A retry restricted to tagged tests is only worth it if at least one of them failed. Otherwise,
+// Robot Framework would error out because no tests match.
+fn retry_tagged_failed_tests(output_path: &Utf8Path, tags: &[String]) -> Retry {
+    match parse_test_results(output_path) {
+        Ok(test_results) => {
+            if test_results.iter().any(|test_result| {
+                test_result.status == TestStatus::Fail
+                    && test_result.tags.iter().any(|tag| tags.contains(tag))
+            }) {
+                Retry::TaggedFailedTests
+            } else {
+                Retry::No
+            }
+        }
+        Err(error) => {
+            warn!("{error:?}, retrying regardless of test tags");
+            Retry::AllFailedTests
+        }
+    }
+}
+
+// The sum of the delays preceding all retries of a run with the given number of attempts.
+pub fn total_retry_delay(delay: &RetryDelay, n_attempts_max: usize) -> Duration {
+    (2..=n_attempts_max)
+        .map(|attempt_index| retry_delay(delay, attempt_index))
+        .sum()
+}
+
+fn retry_delay(delay: &RetryDelay, attempt_index: usize) -> Duration {
+    if attempt_index < 2 {
+        return Duration::ZERO;
+    }
+    Duration::from_secs(match delay {
+        RetryDelay::None => 0,
+        RetryDelay::Constant { seconds } => *seconds,
+        RetryDelay::Exponential {
+            initial_seconds,
+            factor,
+            max_seconds,
+        } => u32::try_from(attempt_index - 2)
+            .ok()
+            .and_then(|exponent| factor.checked_pow(exponent))
+            .and_then(|multiplier| initial_seconds.checked_mul(multiplier))
+            .map_or(*max_seconds, |seconds| seconds.min(*max_seconds)),
+    })
+}
+
+#[tokio::main]
+async fn wait(duration: Duration, cancellation_token: &CancellationToken) -> Result<(), Cancelled> {
+    match waited(duration, cancellation_token, pending::<()>()).await {
+        Outcome::Cancel => Err(Cancelled {}),
+        _ => Ok(()),
+    }
+}
+
 fn run_attempt(
-    id: &str,
-    environment: &Environment,
-    session: &Session,
-    timeout: u64,
+    spec: &PlanRunSpec,
     attempt: Attempt,
-    cancellation_token: &CancellationToken,
-    output_directory: &Utf8Path,
-) -> Result<(AttemptOutcome, Option<Utf8PathBuf>), Cancelled> {
+    timeout: u64,
+) -> Result<(AttemptOutcome, Option<Utf8PathBuf>, ProcessReport), Cancelled> {
+    let &PlanRunSpec {
+        id,
+        robot,
+        environment,
+        session,
+        cancellation_token,
+        output_directory,
+        ..
+    } = spec;
     let log_message_start = format!("Plan {}, attempt {}", id, attempt.index);
 
-    let run_outcome = match session
-        .run(&RunSpec {
+    let (run_outcome, process_report) = match session
+        .run_with_report(&RunSpec {
             id: &format!("robotmk_plan_{}_attempt_{}", id, attempt.index),
             command_spec: &environment.wrap(attempt.command_spec),
             runtime_base_path: &output_directory.join(attempt.index.to_string()),
             timeout,
             cancellation_token,
+            termination_ladder: &robot.termination_ladder,
         })
         .context("Plan execution failed")
     {
         Ok(run_outcome) => run_outcome,
         Err(error_) => {
             error!("{log_message_start}: {error_:?}");
-            return Ok((AttemptOutcome::OtherError(format!("{error_:?}")), None));
+            return Ok((
+                AttemptOutcome::OtherError(format!("{error_:?}")),
+                None,
+                ProcessReport::default(),
+            ));
         }
     };
     let exit_code = match run_outcome {
         Outcome::Completed(exit_code) => exit_code,
         Outcome::Timeout => {
             error!("{log_message_start}: robot run timed out");
-            return Ok((AttemptOutcome::TimedOut, None));
+            return Ok((AttemptOutcome::TimedOut, None, process_report));
         }
         Outcome::Cancel => {
             error!("{log_message_start}: robot run was cancelled");
@@ -113,25 +311,34 @@ fn run_attempt(
             Ok((
                 AttemptOutcome::AllTestsPassed,
                 Some(attempt.output_xml_file),
+                process_report,
             ))
         }
         ResultCode::EnvironmentFailed => {
             error!("{log_message_start}: environment failure");
-            Ok((AttemptOutcome::EnvironmentFailure, None))
+            Ok((AttemptOutcome::EnvironmentFailure, None, process_report))
         }
         ResultCode::WrappedCommandFailed => {
             if attempt.output_xml_file.exists() {
                 info!("{log_message_start}: some tests failed");
-                Ok((AttemptOutcome::TestFailures, Some(attempt.output_xml_file)))
+                Ok((
+                    AttemptOutcome::TestFailures,
+                    Some(attempt.output_xml_file),
+                    process_report,
+                ))
             } else {
                 error!("{log_message_start}: robot failure (no output)");
-                Ok((AttemptOutcome::RobotFailure, None))
+                Ok((AttemptOutcome::RobotFailure, None, process_report))
             }
         }
         ResultCode::Error(error) => {
             if attempt.output_xml_file.exists() {
                 info!("{log_message_start}: some tests failed");
-                Ok((AttemptOutcome::TestFailures, Some(attempt.output_xml_file)))
+                Ok((
+                    AttemptOutcome::TestFailures,
+                    Some(attempt.output_xml_file),
+                    process_report,
+                ))
             } else {
                 error!("{log_message_start}: {error} (no output)");
                 Ok((
@@ -139,8 +346,177 @@ fn run_attempt(
                         "{error} (no output), see {output_directory} for stdio logs"
                     )),
                     None,
+                    process_report,
                 ))
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::write;
+
+    #[test]
+    fn should_retry_respects_triggers() {
+        let retry_policy = RetryPolicyConfig {
+            retry_on: vec![RetryTrigger::TimedOut, RetryTrigger::RobotFailure],
+            retry_test_tags: vec![],
+            delay: RetryDelay::None,
+        };
+        assert_eq!(
+            should_retry(&retry_policy, &AttemptOutcome::TimedOut, None),
+            Retry::AllFailedTests
+        );
+        assert_eq!(
+            should_retry(&retry_policy, &AttemptOutcome::RobotFailure, None),
+            Retry::AllFailedTests
+        );
+        assert_eq!(
+            should_retry(
+                &retry_policy,
+                &AttemptOutcome::TestFailures,
+                Some(Utf8Path::new("1.xml"))
+            ),
+            Retry::No
+        );
+        assert_eq!(
+            should_retry(
+                &retry_policy,
+                &AttemptOutcome::AllTestsPassed,
+                Some(Utf8Path::new("1.xml"))
+            ),
+            Retry::No
+        );
+    }
+
+    #[test]
+    fn should_retry_restricts_to_tags_only_after_test_failures() {
+        let retry_policy = RetryPolicyConfig {
+            retry_test_tags: vec!["flaky".into()],
+            ..RetryPolicyConfig::default()
+        };
+        let directory = tempfile::tempdir().unwrap();
+        let tagged_failure = Utf8PathBuf::try_from(directory.path().join("1.xml")).unwrap();
+        write(
+            &tagged_failure,
+            output_xml(r#"<test name="Division"><tag>flaky</tag><status status="FAIL"/></test>"#),
+        )
+        .unwrap();
+        let untagged_failure = Utf8PathBuf::try_from(directory.path().join("2.xml")).unwrap();
+        write(
+            &untagged_failure,
+            output_xml(r#"<test name="Division"><tag>smoke</tag><status status="FAIL"/></test>"#),
+        )
+        .unwrap();
+
+        assert_eq!(
+            should_retry(
+                &retry_policy,
+                &AttemptOutcome::TestFailures,
+                Some(&tagged_failure)
+            ),
+            Retry::TaggedFailedTests
+        );
+        assert_eq!(
+            should_retry(
+                &retry_policy,
+                &AttemptOutcome::TestFailures,
+                Some(&untagged_failure)
+            ),
+            Retry::No
+        );
+        for outcome in [
+            AttemptOutcome::TimedOut,
+            AttemptOutcome::RobotFailure,
+            AttemptOutcome::EnvironmentFailure,
+        ] {
+            assert_eq!(
+                should_retry(&retry_policy, &outcome, None),
+                Retry::AllFailedTests
+            );
+        }
+    }
+
+    fn output_xml(tests: &str) -> String {
+        format!(r#"<robot><suite name="Tasks">{tests}</suite></robot>"#)
+    }
+
+    #[test]
+    fn budget_unlimited() {
+        let budget = Budget {
+            start: Instant::now() - Duration::from_secs(1000),
+            total_budget: None,
+        };
+        assert!(!budget.is_exhausted_after(Duration::from_secs(1000)));
+        assert_eq!(budget.attempt_timeout(60), 60);
+        assert_eq!(budget.rebot_timeout(), REBOT_TIMEOUT);
+    }
+
+    #[test]
+    fn budget_limits_attempts_and_reserves_rebot() {
+        let total_budget = TotalBudgetConfig {
+            budget: 300,
+            rebot_reserve: 60,
+        };
+        let budget = Budget {
+            start: Instant::now() - Duration::from_secs(200),
+            total_budget: Some(&total_budget),
+        };
+        assert!(budget.attempt_timeout(60) <= 40);
+        assert!(budget.attempt_timeout(60) >= 39);
+        assert!(!budget.is_exhausted_after(Duration::from_secs(10)));
+        assert!(budget.is_exhausted_after(Duration::from_secs(40)));
+        assert!(budget.rebot_timeout() >= 99);
+    }
+
+    #[test]
+    fn budget_guarantees_rebot_reserve() {
+        let total_budget = TotalBudgetConfig {
+            budget: 300,
+            rebot_reserve: 60,
+        };
+        let budget = Budget {
+            start: Instant::now() - Duration::from_secs(290),
+            total_budget: Some(&total_budget),
+        };
+        assert!(budget.is_exhausted_after(Duration::ZERO));
+        assert_eq!(budget.attempt_timeout(60), 1);
+        assert_eq!(budget.rebot_timeout(), 60);
+    }
+
+    #[test]
+    fn total_retry_delay_sums_up_retries() {
+        let delay = RetryDelay::Exponential {
+            initial_seconds: 5,
+            factor: 2,
+            max_seconds: 30,
+        };
+        assert_eq!(total_retry_delay(&delay, 1).as_secs(), 0);
+        assert_eq!(total_retry_delay(&delay, 4).as_secs(), 35);
+    }
+
+    #[test]
+    fn retry_delay_exponential() {
+        let delay = RetryDelay::Exponential {
+            initial_seconds: 5,
+            factor: 2,
+            max_seconds: 30,
+        };
+        assert_eq!(
+            (1..=6)
+                .map(|index| retry_delay(&delay, index).as_secs())
+                .collect::<Vec<_>>(),
+            [0, 5, 10, 20, 30, 30]
+        );
+    }
+
+    #[test]
+    fn retry_delay_constant() {
+        assert_eq!(
+            retry_delay(&RetryDelay::Constant { seconds: 7 }, 3),
+            Duration::from_secs(7)
+        );
+    }
+}
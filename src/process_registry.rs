@@ -0,0 +1,173 @@
+use crate::termination::kill_process_tree;
+
+use anyhow::{Context, Result as AnyhowResult};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{read_dir, read_to_string, remove_file, write};
+use std::sync::OnceLock;
+use sysinfo::{Pid, ProcessesToUpdate, System};
+
+// Records the supervised child processes of the scheduler, such that survivors can be terminated
+// after a crash. The registry is process-wide, since children are spawned from many places. As
+// long as it is not initialized, nothing is recorded.
+// Only the direct children are recorded (e.g. python, rcc or micromamba). Their descendants, such
+// as browsers, are terminated as part of the process tree of a recorded survivor. Descendants whose
+// recorded ancestor already exited have been reparented and are out of reach (on Linux, the cgroup
+// containment covers this case).
+static REGISTRY_DIRECTORY: OnceLock<Utf8PathBuf> = OnceLock::new();
+
+pub fn process_registry_directory(state_directory: &Utf8Path) -> Utf8PathBuf {
+    state_directory.join("processes")
+}
+
+pub fn init(directory: &Utf8Path) {
+    let _ = REGISTRY_DIRECTORY.set(directory.to_path_buf());
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct ProcessRecord {
+    pid: u32,
+    start_time: u64,
+    command: String,
+}
+
+// Removes the record once the process has been waited for.
+pub struct Registration {
+    path: Utf8PathBuf,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Err(error) = remove_file(&self.path) {
+            error!("Failed to remove process record {}: {error}", self.path);
+        }
+    }
+}
+
+pub fn register(pid: u32, command: &str) -> Option<Registration> {
+    let directory = REGISTRY_DIRECTORY.get()?;
+    let Some(start_time) = start_time(pid) else {
+        debug!("Process {pid} exited before it could be registered");
+        return None;
+    };
+    let path = directory.join(format!("{pid}.json"));
+    match serde_json::to_string(&ProcessRecord {
+        pid,
+        start_time,
+        command: command.to_string(),
+    })
+    .context("Failed to serialize process record")
+    .and_then(|record| write(&path, record).context(format!("Failed to write {path}")))
+    {
+        Ok(()) => Some(Registration { path }),
+        Err(error) => {
+            error!("{error:?}");
+            None
+        }
+    }
+}
+
+// Terminates the processes recorded by a previous incarnation of the scheduler which are still
+// alive. The start time guards against killing unrelated processes which reuse a recorded PID.
+// Faulty records are logged and skipped, they must not prevent the scheduler from starting.
+pub fn reap_orphans(directory: &Utf8Path) -> AnyhowResult<()> {
+    if !directory.exists() {
+        return Ok(());
+    }
+    for entry in read_dir(directory).context(format!("Failed to read {directory}"))? {
+        if let Err(error) = entry
+            .context(format!("Failed to read entry of {directory}"))
+            .and_then(|entry| {
+                Utf8PathBuf::try_from(entry.path()).context(format!(
+                    "Non-UTF-8 process record {}",
+                    entry.path().display()
+                ))
+            })
+            .and_then(|path| reap_orphan(&path))
+        {
+            warn!("{error:?}");
+        }
+    }
+    Ok(())
+}
+
+fn reap_orphan(path: &Utf8Path) -> AnyhowResult<()> {
+    match read_to_string(path)
+        .context(format!("Failed to read {path}"))
+        .and_then(|content| {
+            serde_json::from_str::<ProcessRecord>(&content)
+                .context(format!("Failed to parse {path}"))
+        }) {
+        Ok(record) if start_time(record.pid) == Some(record.start_time) => {
+            warn!(
+                "Terminating orphaned process {} from previous scheduler run: {}",
+                record.pid, record.command
+            );
+            kill_process_tree(&Pid::from_u32(record.pid));
+        }
+        Ok(_) => {}
+        Err(error) => warn!("{error:?}"),
+    }
+    remove_file(path).context(format!("Failed to remove {path}"))
+}
+
+fn start_time(pid: u32) -> Option<u64> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    system.process(pid).map(|process| process.start_time())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    #[test]
+    #[cfg(unix)]
+    fn reap_orphans_kills_recorded_survivors_only() {
+        let directory = tempdir().unwrap();
+        let directory = Utf8Path::from_path(directory.path()).unwrap();
+        let mut survivor = Command::new("sleep").arg("30").spawn().unwrap();
+        let survivor_pid = survivor.id();
+        for record in [
+            ProcessRecord {
+                pid: survivor_pid,
+                start_time: start_time(survivor_pid).unwrap(),
+                command: "sleep 30".into(),
+            },
+            // Same PID, different start time: the PID was reused by an unrelated process.
+            ProcessRecord {
+                pid: std::process::id(),
+                start_time: 0,
+                command: "unrelated".into(),
+            },
+        ] {
+            write(
+                directory.join(format!("{}.json", record.pid)),
+                serde_json::to_string(&record).unwrap(),
+            )
+            .unwrap();
+        }
+
+        reap_orphans(directory).unwrap();
+
+        assert!(!survivor.wait().unwrap().success());
+        assert!(read_dir(directory).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn reap_orphans_skips_faulty_records() {
+        let directory = tempdir().unwrap();
+        let directory = Utf8Path::from_path(directory.path()).unwrap();
+        std::fs::create_dir(directory.join("not_a_record")).unwrap();
+        write(directory.join("garbage.json"), "{").unwrap();
+
+        reap_orphans(directory).unwrap();
+
+        assert!(directory.join("not_a_record").exists());
+        assert!(!directory.join("garbage.json").exists());
+    }
+}
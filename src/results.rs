@@ -1,18 +1,39 @@
-use crate::config::PlanMetadata;
+use crate::config::{MissedTickPolicy, PlanMetadata, TerminationSignal, TotalBudgetConfig};
+use crate::history::RunOutcome;
 use crate::section::{WritePiggybackSection, WriteSection};
 use camino::{Utf8Path, Utf8PathBuf};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub fn results_directory(runtime_directory: &Utf8Path) -> Utf8PathBuf {
     runtime_directory.join("results")
 }
 
+pub fn scheduler_phase_file(results_directory: &Utf8Path) -> Utf8PathBuf {
+    results_directory.join("scheduler_phase.json")
+}
+
+pub fn live_status_file(results_directory: &Utf8Path) -> Utf8PathBuf {
+    results_directory.join("live_status.json")
+}
+
+pub fn scheduler_heartbeat_file(results_directory: &Utf8Path) -> Utf8PathBuf {
+    results_directory.join("scheduler_heartbeat.json")
+}
+
+pub fn plans_working_directory(runtime_directory: &Utf8Path) -> Utf8PathBuf {
+    runtime_directory.join("working").join("plans")
+}
+
+pub fn state_directory(runtime_directory: &Utf8Path) -> Utf8PathBuf {
+    runtime_directory.join("state")
+}
+
 pub fn plan_results_directory(results_directory: &Utf8Path) -> Utf8PathBuf {
     results_directory.join("plans")
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum SchedulerPhase {
     GracePeriod(u64),
     Setup,
@@ -26,6 +47,103 @@ impl WriteSection for SchedulerPhase {
     }
 }
 
+// Rewritten regularly, such that a frozen or crashed scheduler can be told apart from a healthy one
+// which has nothing new to report.
+pub const SCHEDULER_HEARTBEAT_INTERVAL_SECS: u64 = 30;
+
+// Timestamps are Unix timestamps, the uptime is in seconds. The config digest is the SHA-256 hash
+// of the configuration file the scheduler was started with. The progress is the time at which the
+// plan group lagging furthest behind last completed a run (or started to be scheduled). It is only
+// set while scheduling and reveals groups which hang, even though the heartbeat itself is fresh.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct SchedulerHeartbeat {
+    pub timestamp: i64,
+    pub pid: u32,
+    pub start_time: i64,
+    pub uptime: u64,
+    pub version: String,
+    pub config_digest: String,
+    pub phase: SchedulerPhase,
+    pub progress: Option<i64>,
+}
+
+impl WriteSection for SchedulerHeartbeat {
+    fn name() -> &'static str {
+        "robotmk_scheduler_heartbeat"
+    }
+}
+
+// Computed by the agent plugin. A plan report is stale if it is older than the execution interval
+// of its plan plus the maximum runtime of the plan. The heartbeat is stale if it is missing or if
+// several beats were missed. The progress is stale if no plan group completed a run for longer than
+// the largest maximum age of any plan. Ages are in seconds.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct StaleResults {
+    pub scheduler_heartbeat_age: Option<i64>,
+    pub scheduler_heartbeat_stale: bool,
+    pub scheduler_progress_age: Option<i64>,
+    pub scheduler_progress_stale: bool,
+    pub plans: Vec<StaleResult>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct StaleResult {
+    pub plan_id: String,
+    pub timestamp: i64,
+    pub age: i64,
+    pub max_age: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LiveStatus(pub Vec<PlanLiveStatus>);
+
+impl WriteSection for LiveStatus {
+    fn name() -> &'static str {
+        "robotmk_live_status"
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PlanLiveStatus {
+    pub plan_id: String,
+    pub activity: PlanActivity,
+    pub next_scheduled_run: Option<i64>,
+    pub last_outcome: Option<RunOutcome>,
+}
+
+// Timestamps are Unix timestamps.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum PlanActivity {
+    Idle,
+    Building { since: i64 },
+    Running { since: i64 },
+    RunningAttempt { index: usize, since: i64 },
+    RunningRebot { since: i64 },
+}
+
+#[derive(Clone, Serialize)]
+pub struct SchedulingStatus(pub Vec<GroupSchedulingStatus>);
+
+impl WriteSection for SchedulingStatus {
+    fn name() -> &'static str {
+        "robotmk_scheduling_status"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct GroupSchedulingStatus {
+    pub plan_ids: Vec<String>,
+    pub execution_interval: u64,
+    pub missed_tick_policy: MissedTickPolicy,
+    pub last_intended_start: Option<i64>,
+    pub last_actual_start: Option<i64>,
+    pub last_lag_millis: u64,
+    pub max_lag_millis: u64,
+    pub last_run_duration: Option<u64>,
+    pub overruns: usize,
+    pub skipped_ticks: u64,
+}
+
 #[derive(Serialize)]
 pub struct SetupFailures(pub Vec<SetupFailure>);
 
@@ -55,8 +173,11 @@ impl WriteSection for BuildStates<'_> {
 pub enum BuildOutcome {
     NotNeeded,
     Success(i64),
-    Timeout,
+    Timeout {
+        terminated_by: Option<TerminationSignal>,
+    },
     Error(String),
+    DryRunFailure(Vec<String>),
 }
 
 #[derive(Serialize)]
@@ -74,6 +195,18 @@ pub struct PlanExecutionReport {
     pub rebot: Option<RebotOutcome>,
     pub config: AttemptsConfig,
     pub metadata: PlanMetadata,
+    pub hooks: Vec<HookReport>,
+    pub aborted_by_pre_run_hook: bool,
+    pub flakiness: Vec<TestFlakiness>,
+    pub failed_dependency: Option<FailedDependency>,
+}
+
+// The prerequisite plan whose latest run did not pass, causing the dependent plan to be skipped.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FailedDependency {
+    pub plan_id: String,
+    pub outcome: RunOutcome,
+    pub timestamp: i64,
 }
 
 impl WritePiggybackSection for PlanExecutionReport {
@@ -87,6 +220,17 @@ pub struct AttemptReport {
     pub index: usize,
     pub outcome: AttemptOutcome,
     pub runtime: i64,
+    pub terminated_by: Option<TerminationSignal>,
+    pub resource_usage: Option<ResourceUsage>,
+}
+
+// Only available if the attempt was contained in a cgroup. Peak memory additionally requires the
+// memory controller.
+#[derive(PartialEq, Debug, Serialize)]
+pub struct ResourceUsage {
+    pub user_cpu_seconds: f64,
+    pub system_cpu_seconds: f64,
+    pub peak_memory_bytes: Option<u64>,
 }
 
 #[derive(PartialEq, Debug, Serialize)]
@@ -99,9 +243,44 @@ pub enum AttemptOutcome {
     OtherError(String),
 }
 
+#[derive(PartialEq, Debug, Serialize)]
+pub struct TestFlakiness {
+    pub test: String,
+    pub needed_retries_in_latest_run: bool,
+    pub runs: usize,
+    pub runs_with_retries: usize,
+    pub score: f64,
+}
+
+#[derive(PartialEq, Debug, Serialize)]
+pub struct HookReport {
+    pub name: String,
+    pub stage: HookStage,
+    pub outcome: HookOutcome,
+    pub runtime: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+pub enum HookStage {
+    PreRun,
+    PostRun,
+}
+
+#[derive(PartialEq, Debug, Serialize)]
+pub enum HookOutcome {
+    Success,
+    Failure(i32),
+    EnvironmentFailure,
+    TimedOut,
+    OtherError(String),
+}
+
 #[derive(Debug, Serialize)]
 pub enum RebotOutcome {
     Ok(RebotResult),
+    Timeout {
+        terminated_by: Option<TerminationSignal>,
+    },
     Error(String),
 }
 
@@ -117,6 +296,7 @@ pub struct AttemptsConfig {
     pub interval: u64,
     pub timeout: u64,
     pub n_attempts_max: usize,
+    pub total_budget: Option<TotalBudgetConfig>,
 }
 
 #[derive(Serialize)]
@@ -0,0 +1,170 @@
+use super::robot::Robot;
+use crate::config::TerminationSignal;
+use crate::env::{Environment, ResultCode};
+use crate::session::{RunSpec, Session};
+use crate::termination::{Cancelled, Outcome};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use log::{error, info};
+use std::fs::read_to_string;
+use tokio_util::sync::CancellationToken;
+
+pub struct DryRun<'a> {
+    pub plan_id: &'a str,
+    pub robot: &'a Robot,
+    pub environment: &'a Environment,
+    pub session: &'a Session,
+    pub output_directory: &'a Utf8Path,
+    pub timeout: u64,
+    pub cancellation_token: &'a CancellationToken,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DryRunOutcome {
+    Passed,
+    Failed(Vec<String>),
+    TimedOut {
+        terminated_by: Option<TerminationSignal>,
+    },
+    Error(String),
+}
+
+impl DryRun<'_> {
+    pub fn run(&self) -> Result<DryRunOutcome, Cancelled> {
+        let runtime_base_path = self.output_directory.join("dry_run");
+        let exit_code = match self.session.run_with_report(&RunSpec {
+            id: &format!("robotmk_dry_run_{}", self.plan_id),
+            command_spec: &self
+                .environment
+                .wrap(self.robot.dry_run_command_spec(self.output_directory)),
+            runtime_base_path: &runtime_base_path,
+            timeout: self.timeout,
+            cancellation_token: self.cancellation_token,
+            termination_ladder: &self.robot.termination_ladder,
+        }) {
+            Ok((Outcome::Completed(exit_code), _)) => exit_code,
+            Ok((Outcome::Timeout, process_report)) => {
+                error!("Plan {}: dry run timed out", self.plan_id);
+                return Ok(DryRunOutcome::TimedOut {
+                    terminated_by: process_report.terminated_by,
+                });
+            }
+            Ok((Outcome::Cancel, _)) => {
+                error!("Plan {}: dry run was cancelled", self.plan_id);
+                return Err(Cancelled {});
+            }
+            Err(error) => {
+                error!("Plan {}: dry run failed: {error:?}", self.plan_id);
+                return Ok(DryRunOutcome::Error(format!(
+                    "Dry run failed: {error:?}, see {runtime_base_path} for stdio logs"
+                )));
+            }
+        };
+        match self.environment.create_result_code(exit_code) {
+            ResultCode::Success => {
+                info!("Plan {}: dry run passed", self.plan_id);
+                Ok(DryRunOutcome::Passed)
+            }
+            ResultCode::EnvironmentFailed => {
+                error!("Plan {}: environment failure during dry run", self.plan_id);
+                Ok(DryRunOutcome::Error(format!(
+                    "Environment failure during dry run, see {runtime_base_path} for stdio logs"
+                )))
+            }
+            ResultCode::WrappedCommandFailed | ResultCode::Error(_) => {
+                let mut errors = parse_dry_run_errors(
+                    &read_to_string(Utf8PathBuf::from(format!("{runtime_base_path}.stdout")))
+                        .unwrap_or_default(),
+                    &read_to_string(Utf8PathBuf::from(format!("{runtime_base_path}.stderr")))
+                        .unwrap_or_default(),
+                );
+                if errors.is_empty() {
+                    errors.push(format!(
+                        "Dry run failed with exit code {exit_code}, see {runtime_base_path} for stdio logs"
+                    ));
+                }
+                error!(
+                    "Plan {}: dry run failed:\n{}",
+                    self.plan_id,
+                    errors.join("\n")
+                );
+                Ok(DryRunOutcome::Failed(errors))
+            }
+        }
+    }
+}
+
+// Robot Framework reports syntax and import errors on stderr (`[ ERROR ] ...`) and failing tests on
+// stdout, where the status line (`<test name> | FAIL |`) is followed by the failure message.
+fn parse_dry_run_errors(stdout: &str, stderr: &str) -> Vec<String> {
+    let mut errors: Vec<String> = stderr
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("[ ERROR ]"))
+        .map(|message| message.trim().to_string())
+        .collect();
+    let mut lines = stdout.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(name) = line.trim_end().strip_suffix("| FAIL |") else {
+            continue;
+        };
+        let Some(message) = lines.peek().map(|message| message.trim()) else {
+            continue;
+        };
+        if message.is_empty()
+            || message.starts_with("---")
+            || message.starts_with("===")
+            || is_statistics_line(message)
+        {
+            continue;
+        }
+        errors.push(format!("{}: {message}", name.trim()));
+    }
+    errors
+}
+
+// Suite status lines are followed by statistics (e.g. `2 tests, 1 passed, 1 failed`) instead of
+// a failure message.
+fn is_statistics_line(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    matches!(
+        (words.next().map(|word| word.parse::<usize>()), words.next()),
+        (Some(Ok(_)), Some("test," | "tests," | "task," | "tasks,"))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dry_run_errors_from_stdio() {
+        let stdout =
+            "==============================================================================
+Tasks
+==============================================================================
+Passing Task                                                          | PASS |
+------------------------------------------------------------------------------
+Broken Task                                                           | FAIL |
+No keyword with name 'Does Not Exist' found.
+------------------------------------------------------------------------------
+Tasks                                                                 | FAIL |
+2 tasks, 1 passed, 1 failed
+==============================================================================
+";
+        let stderr =
+            "[ ERROR ] Error in file '/suite/tasks.robot' on line 4: Non-existing setting 'Foo'.
+";
+        assert_eq!(
+            parse_dry_run_errors(stdout, stderr),
+            [
+                "Error in file '/suite/tasks.robot' on line 4: Non-existing setting 'Foo'.",
+                "Broken Task: No keyword with name 'Does Not Exist' found."
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_dry_run_errors_empty() {
+        assert!(parse_dry_run_errors("", "").is_empty());
+    }
+}
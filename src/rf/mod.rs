@@ -1,2 +1,4 @@
+pub mod dry_run;
+pub mod output;
 pub mod rebot;
 pub mod robot;
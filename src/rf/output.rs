@@ -0,0 +1,129 @@
+use anyhow::{Context, Result as AnyhowResult};
+use camino::Utf8Path;
+use roxmltree::{Document, Node};
+use std::fs::read_to_string;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub status: TestStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TestStatus {
+    Pass,
+    Fail,
+    Skip,
+    NotRun,
+}
+
+pub fn parse_test_results(output_xml_file: &Utf8Path) -> AnyhowResult<Vec<TestResult>> {
+    test_results_from_xml(
+        &read_to_string(output_xml_file).context(format!("Failed to read {output_xml_file}"))?,
+    )
+    .context(format!("Failed to parse {output_xml_file}"))
+}
+
+pub fn test_results_from_xml(xml: &str) -> AnyhowResult<Vec<TestResult>> {
+    let document = Document::parse(xml)?;
+    Ok(document
+        .descendants()
+        .filter(|node| node.has_tag_name("test"))
+        .map(|test| TestResult {
+            name: full_name(test),
+            tags: tags(test),
+            status: status(test),
+        })
+        .collect())
+}
+
+// The full name of a test consists of the names of all enclosing suites and the test name, joined
+// by dots, just like Robot Framework's `longname`.
+fn full_name(test: Node) -> String {
+    let mut names: Vec<&str> = test
+        .ancestors()
+        .filter(|node| node.has_tag_name("suite") || node.has_tag_name("test"))
+        .filter_map(|node| node.attribute("name"))
+        .collect();
+    names.reverse();
+    names.join(".")
+}
+
+// Robot Framework 7 puts `<tag>` elements directly below `<test>`, older versions wrap them in
+// `<tags>`.
+fn tags(test: Node) -> Vec<String> {
+    test.children()
+        .flat_map(|child| {
+            if child.has_tag_name("tags") {
+                child.children().collect()
+            } else {
+                vec![child]
+            }
+        })
+        .filter(|node| node.has_tag_name("tag"))
+        .filter_map(|tag| tag.text())
+        .map(str::to_string)
+        .collect()
+}
+
+fn status(test: Node) -> TestStatus {
+    match test
+        .children()
+        .rfind(|child| child.has_tag_name("status"))
+        .and_then(|status| status.attribute("status"))
+    {
+        Some("PASS") => TestStatus::Pass,
+        Some("FAIL") => TestStatus::Fail,
+        Some("SKIP") => TestStatus::Skip,
+        _ => TestStatus::NotRun,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_from_output_xml() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<robot generator="Robot 7.0 (Python 3.12.0 on linux)" rpa="false" schemaversion="5">
+<suite id="s1" name="Tasks">
+<suite id="s1-s1" name="Calculator">
+<test id="s1-s1-t1" name="Addition" line="3">
+<kw name="Log">
+<status status="FAIL" start="2024-01-01T00:00:00.000000" elapsed="0.001"/>
+</kw>
+<tag>flaky</tag>
+<tag>smoke</tag>
+<status status="PASS" start="2024-01-01T00:00:00.000000" elapsed="0.002"/>
+</test>
+<test id="s1-s1-t2" name="Division" line="7">
+<tags>
+<tag>flaky</tag>
+</tags>
+<status status="FAIL" start="2024-01-01T00:00:00.000000" elapsed="0.002">Division by zero</status>
+</test>
+<status status="FAIL" start="2024-01-01T00:00:00.000000" elapsed="0.004"/>
+</suite>
+<status status="FAIL" start="2024-01-01T00:00:00.000000" elapsed="0.005"/>
+</suite>
+</robot>
+"#;
+        assert_eq!(
+            test_results_from_xml(xml).unwrap(),
+            [
+                TestResult {
+                    name: "Tasks.Calculator.Addition".into(),
+                    tags: vec!["flaky".into(), "smoke".into()],
+                    status: TestStatus::Pass,
+                },
+                TestResult {
+                    name: "Tasks.Calculator.Division".into(),
+                    tags: vec!["flaky".into()],
+                    status: TestStatus::Fail,
+                },
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use super::robot::PYTHON_EXECUTABLE;
+use crate::child_process_supervisor::ProcessReport;
 use crate::command_spec::CommandSpec;
+use crate::config::TerminationLadder;
 use crate::env::{Environment, ResultCode};
 use crate::results::{RebotOutcome, RebotResult};
 use crate::session::{RunSpec, Session};
@@ -13,6 +15,8 @@ use log::error;
 use std::fs::{read, read_to_string};
 use tokio_util::sync::CancellationToken;
 
+pub const REBOT_TIMEOUT: u64 = 120;
+
 pub struct Rebot<'a> {
     pub plan_id: &'a str,
     pub environment: &'a Environment,
@@ -22,6 +26,8 @@ pub struct Rebot<'a> {
     pub input_paths: &'a [Utf8PathBuf],
     pub path_xml: &'a Utf8Path,
     pub path_html: &'a Utf8Path,
+    pub timeout: u64,
+    pub termination_ladder: &'a TerminationLadder,
 }
 
 impl Rebot<'_> {
@@ -37,12 +43,14 @@ impl Rebot<'_> {
             }
         };
         let exit_code = match outcome {
-            Outcome::Completed(exit_code) => exit_code,
-            Outcome::Timeout => {
+            (Outcome::Completed(exit_code), _) => exit_code,
+            (Outcome::Timeout, process_report) => {
                 error!("Rebot run timed out");
-                return Ok(RebotOutcome::Error("Timeout".into()));
+                return Ok(RebotOutcome::Timeout {
+                    terminated_by: process_report.terminated_by,
+                });
             }
-            Outcome::Cancel => {
+            (Outcome::Cancel, _) => {
                 error!("Rebot run was cancelled");
                 return Err(Cancelled {});
             }
@@ -81,13 +89,14 @@ impl Rebot<'_> {
         }
     }
 
-    fn run(&self) -> AnyhowResult<Outcome<i32>> {
-        self.session.run(&RunSpec {
+    fn run(&self) -> AnyhowResult<(Outcome<i32>, ProcessReport)> {
+        self.session.run_with_report(&RunSpec {
             id: &format!("robotmk_rebot_{}", self.plan_id),
             command_spec: &self.environment.wrap(self.build_rebot_command_spec()),
             runtime_base_path: &self.runtime_base_path,
-            timeout: 120,
+            timeout: self.timeout,
             cancellation_token: self.cancellation_token,
+            termination_ladder: self.termination_ladder,
         })
     }
 
@@ -156,6 +165,8 @@ mod tests {
             ],
             path_xml: &Utf8PathBuf::from("/working/my_plan/rebot.xml"),
             path_html: &Utf8PathBuf::from("/working/my_plan/rebot.html"),
+            timeout: REBOT_TIMEOUT,
+            termination_ladder: &TerminationLadder::default(),
         }
         .build_rebot_command_spec();
         let mut expected = CommandSpec::new("python");
@@ -1,9 +1,13 @@
 use crate::command_spec::CommandSpec;
-use crate::config::{RetryStrategy, RobotConfig};
+use crate::config::{
+    RetryPolicyConfig, RetryStrategy, RobotConfig, RobotExecutionMode, TerminationLadder,
+    TotalBudgetConfig,
+};
 
 use camino::{Utf8Path, Utf8PathBuf};
 
 pub const PYTHON_EXECUTABLE: &str = "python";
+pub const PABOT_MODULE: &str = "pabot.pabot";
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Robot {
@@ -12,6 +16,10 @@ pub struct Robot {
     pub envs_rendered_obfuscated: Vec<(String, String)>,
     pub n_attempts_max: usize,
     pub retry_strategy: RetryStrategy,
+    pub execution_mode: RobotExecutionMode,
+    pub retry_policy: RetryPolicyConfig,
+    pub total_budget: Option<TotalBudgetConfig>,
+    pub termination_ladder: TerminationLadder,
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq))]
@@ -26,6 +34,9 @@ impl Robot {
         robot_config: RobotConfig,
         n_attempts_max: usize,
         retry_strategy: RetryStrategy,
+        retry_policy: RetryPolicyConfig,
+        total_budget: Option<TotalBudgetConfig>,
+        termination_ladder: TerminationLadder,
     ) -> Self {
         Self {
             robot_target: robot_config.robot_target.clone(),
@@ -34,9 +45,13 @@ impl Robot {
                 .iter()
                 .map(|var| (var.name.clone(), var.value.clone()))
                 .collect(),
+            execution_mode: robot_config.execution_mode.clone(),
             command_line_args: Self::config_to_command_line_args(robot_config),
             n_attempts_max,
             retry_strategy,
+            retry_policy,
+            total_budget,
+            termination_ladder,
         }
     }
 
@@ -44,14 +59,26 @@ impl Robot {
         &'a self,
         output_directory: &'a Utf8Path,
     ) -> impl Iterator<Item = Attempt> + 'a {
-        (1..(self.n_attempts_max + 1)).map(move |i| self.attempt(output_directory, i))
+        (1..(self.n_attempts_max + 1)).map(move |i| self.attempt(output_directory, i, false))
     }
 
-    fn attempt(&self, output_directory: &Utf8Path, index: usize) -> Attempt {
+    // `only_tagged_tests` restricts an incremental retry to the failed tests carrying one of the
+    // retry test tags. A complete retry always reruns everything.
+    pub fn attempt(
+        &self,
+        output_directory: &Utf8Path,
+        index: usize,
+        only_tagged_tests: bool,
+    ) -> Attempt {
         let output_xml_file = output_directory.join(format!("{index}.xml"));
         Attempt {
             index,
-            command_spec: self.command_spec(output_directory, &output_xml_file, index),
+            command_spec: self.command_spec(
+                output_directory,
+                &output_xml_file,
+                index,
+                only_tagged_tests,
+            ),
             output_xml_file,
         }
     }
@@ -61,14 +88,34 @@ impl Robot {
         output_directory: &Utf8Path,
         output_xml_file: &Utf8Path,
         index: usize,
+        only_tagged_tests: bool,
     ) -> CommandSpec {
         let mut command_spec = CommandSpec::new(PYTHON_EXECUTABLE);
-        command_spec.add_argument("-m").add_argument("robot");
+        command_spec.add_argument("-m");
+        match &self.execution_mode {
+            RobotExecutionMode::Robot => {
+                command_spec.add_argument("robot");
+            }
+            // Pabot keeps its `.pabotsuitenames` in the current directory, which would collide
+            // between plans running in parallel.
+            RobotExecutionMode::Pabot { processes } => {
+                command_spec
+                    .add_argument(PABOT_MODULE)
+                    .add_argument("--processes")
+                    .add_argument(processes.to_string())
+                    .set_current_directory(output_directory);
+            }
+        }
         command_spec.add_arguments(&self.command_line_args);
         if matches!(self.retry_strategy, RetryStrategy::Incremental) && index > 1 {
             command_spec
                 .add_argument("--rerunfailed")
                 .add_argument(output_directory.join(format!("{}.xml", index - 1)));
+            if only_tagged_tests {
+                for tag in &self.retry_policy.retry_test_tags {
+                    command_spec.add_argument("--include").add_argument(tag);
+                }
+            }
         };
         command_spec
             .add_argument("--outputdir")
@@ -86,6 +133,32 @@ impl Robot {
         command_spec
     }
 
+    // The dry run always uses plain robot, also in pabot mode. It only checks syntax and imports,
+    // which does not depend on how the suites are distributed among processes.
+    pub fn dry_run_command_spec(&self, output_directory: &Utf8Path) -> CommandSpec {
+        let mut command_spec = CommandSpec::new(PYTHON_EXECUTABLE);
+        command_spec
+            .add_argument("-m")
+            .add_argument("robot")
+            .add_argument("--dryrun")
+            .add_arguments(&self.command_line_args)
+            .add_argument("--outputdir")
+            .add_argument(output_directory)
+            .add_argument("--output")
+            .add_argument("NONE")
+            .add_argument("--log")
+            .add_argument("NONE")
+            .add_argument("--report")
+            .add_argument("NONE")
+            .add_argument("--consolecolors")
+            .add_argument("off")
+            .add_argument(&self.robot_target);
+        for (k, v) in &self.envs_rendered_obfuscated {
+            command_spec.add_obfuscated_env(k, v);
+        }
+        command_spec
+    }
+
     fn config_to_command_line_args(robot_config: RobotConfig) -> Vec<String> {
         let mut args = vec![];
         if let Some(top_level_suite_name) = robot_config.top_level_suite_name {
@@ -147,10 +220,14 @@ mod tests {
                     variable_files: vec![],
                     argument_files: vec![],
                     exit_on_failure: false,
-                    environment_variables_rendered_obfuscated: vec![]
+                    environment_variables_rendered_obfuscated: vec![],
+                    execution_mode: RobotExecutionMode::Robot,
                 },
                 1,
-                RetryStrategy::Incremental
+                RetryStrategy::Incremental,
+                RetryPolicyConfig::default(),
+                None,
+                TerminationLadder::default(),
             )
             .command_line_args
             .is_empty(),
@@ -188,9 +265,13 @@ mod tests {
                     ],
                     exit_on_failure: true,
                     environment_variables_rendered_obfuscated: vec![],
+                    execution_mode: RobotExecutionMode::Robot,
                 },
                 1,
-                RetryStrategy::Incremental
+                RetryStrategy::Incremental,
+                RetryPolicyConfig::default(),
+                None,
+                TerminationLadder::default(),
             )
             .command_line_args,
             vec![
@@ -249,10 +330,14 @@ mod tests {
                             name: "NAME".into(),
                             value: "value".into()
                         }
-                    ]
+                    ],
+                    execution_mode: RobotExecutionMode::Robot,
                 },
                 1,
-                RetryStrategy::Incremental
+                RetryStrategy::Incremental,
+                RetryPolicyConfig::default(),
+                None,
+                TerminationLadder::default(),
             )
             .envs_rendered_obfuscated,
             vec![("NAME".into(), "value".into())]
@@ -273,6 +358,10 @@ mod tests {
             ],
             envs_rendered_obfuscated: vec![],
             retry_strategy: RetryStrategy::Complete,
+            execution_mode: RobotExecutionMode::Robot,
+            retry_policy: RetryPolicyConfig::default(),
+            total_budget: None,
+            termination_ladder: TerminationLadder::default(),
         };
         let output_directory =
             Utf8PathBuf::from("/tmp/calculator_plan/2023-08-29T12.23.44.419347+00.00");
@@ -295,7 +384,7 @@ mod tests {
             .add_argument("~/calculator_test/calculator.robot");
         // Act
         let command_spec =
-            robot.command_spec(&output_directory, &output_directory.join("1.xml"), 1);
+            robot.command_spec(&output_directory, &output_directory.join("1.xml"), 1, false);
         // Assert
         assert_eq!(command_spec, expected);
     }
@@ -313,6 +402,10 @@ mod tests {
             ],
             envs_rendered_obfuscated: vec![],
             retry_strategy: RetryStrategy::Incremental,
+            execution_mode: RobotExecutionMode::Robot,
+            retry_policy: RetryPolicyConfig::default(),
+            total_budget: None,
+            termination_ladder: TerminationLadder::default(),
         };
         let output_directory =
             Utf8PathBuf::from("/tmp/calculator_plan/2023-08-29T12.23.44.419347+00.00");
@@ -334,7 +427,7 @@ mod tests {
             .add_argument("~/calculator_test/calculator.robot");
         // Act
         let command_spec =
-            robot.command_spec(&output_directory, &output_directory.join("1.xml"), 1);
+            robot.command_spec(&output_directory, &output_directory.join("1.xml"), 1, false);
         // Assert
         assert_eq!(command_spec, expected);
     }
@@ -348,6 +441,10 @@ mod tests {
             command_line_args: vec![],
             envs_rendered_obfuscated: vec![],
             retry_strategy: RetryStrategy::Incremental,
+            execution_mode: RobotExecutionMode::Robot,
+            retry_policy: RetryPolicyConfig::default(),
+            total_budget: None,
+            termination_ladder: TerminationLadder::default(),
         };
         let output_directory =
             Utf8PathBuf::from("/tmp/calculator_plan/2023-08-29T12.23.44.419347+00.00");
@@ -368,11 +465,160 @@ mod tests {
             .add_argument("~/calculator_test/calculator.robot");
         // Act
         let command_spec =
-            robot.command_spec(&output_directory, &output_directory.join("2.xml"), 2);
+            robot.command_spec(&output_directory, &output_directory.join("2.xml"), 2, false);
+        // Assert
+        assert_eq!(command_spec, expected)
+    }
+
+    #[test]
+    fn create_pabot_incremental_command_second() {
+        // Assemble
+        let robot = Robot {
+            robot_target: "~/calculator_test/calculator.robot".into(),
+            n_attempts_max: 2,
+            command_line_args: vec!["--exitonfailure".into()],
+            envs_rendered_obfuscated: vec![],
+            retry_strategy: RetryStrategy::Incremental,
+            execution_mode: RobotExecutionMode::Pabot { processes: 4 },
+            retry_policy: RetryPolicyConfig::default(),
+            total_budget: None,
+            termination_ladder: TerminationLadder::default(),
+        };
+        let output_directory =
+            Utf8PathBuf::from("/tmp/calculator_plan/2023-08-29T12.23.44.419347+00.00");
+        let mut expected = CommandSpec::new(PYTHON_EXECUTABLE);
+        expected
+            .add_argument("-m")
+            .add_argument(PABOT_MODULE)
+            .add_argument("--processes")
+            .add_argument("4")
+            .set_current_directory(&output_directory)
+            .add_argument("--exitonfailure")
+            .add_argument("--rerunfailed")
+            .add_argument(output_directory.join("1.xml"))
+            .add_argument("--outputdir")
+            .add_argument(&output_directory)
+            .add_argument("--output")
+            .add_argument(output_directory.join("2.xml"))
+            .add_argument("--log")
+            .add_argument(output_directory.join("2.html"))
+            .add_argument("--report")
+            .add_argument("NONE")
+            .add_argument("~/calculator_test/calculator.robot");
+        // Act
+        let command_spec =
+            robot.command_spec(&output_directory, &output_directory.join("2.xml"), 2, false);
         // Assert
         assert_eq!(command_spec, expected)
     }
 
+    #[test]
+    fn create_incremental_command_second_only_tagged_tests() {
+        // Assemble
+        let robot = Robot {
+            robot_target: "~/calculator_test/calculator.robot".into(),
+            n_attempts_max: 2,
+            command_line_args: vec![],
+            envs_rendered_obfuscated: vec![],
+            retry_strategy: RetryStrategy::Incremental,
+            execution_mode: RobotExecutionMode::Robot,
+            retry_policy: RetryPolicyConfig {
+                retry_test_tags: vec!["flaky".into()],
+                ..RetryPolicyConfig::default()
+            },
+            total_budget: None,
+            termination_ladder: TerminationLadder::default(),
+        };
+        let output_directory =
+            Utf8PathBuf::from("/tmp/calculator_plan/2023-08-29T12.23.44.419347+00.00");
+        let mut expected = CommandSpec::new(PYTHON_EXECUTABLE);
+        expected
+            .add_argument("-m")
+            .add_argument("robot")
+            .add_argument("--rerunfailed")
+            .add_argument(output_directory.join("1.xml"))
+            .add_argument("--include")
+            .add_argument("flaky")
+            .add_argument("--outputdir")
+            .add_argument(&output_directory)
+            .add_argument("--output")
+            .add_argument(output_directory.join("2.xml"))
+            .add_argument("--log")
+            .add_argument(output_directory.join("2.html"))
+            .add_argument("--report")
+            .add_argument("NONE")
+            .add_argument("~/calculator_test/calculator.robot");
+        // Act
+        let only_tagged =
+            robot.command_spec(&output_directory, &output_directory.join("2.xml"), 2, true);
+        let all_failed =
+            robot.command_spec(&output_directory, &output_directory.join("2.xml"), 2, false);
+        // Assert
+        assert_eq!(only_tagged, expected);
+        assert!(!all_failed.arguments.contains(&"--include".to_string()));
+    }
+
+    #[test]
+    fn create_complete_command_second_ignores_retry_test_tags() {
+        let robot = Robot {
+            robot_target: "~/calculator_test/calculator.robot".into(),
+            n_attempts_max: 2,
+            command_line_args: vec![],
+            envs_rendered_obfuscated: vec![],
+            retry_strategy: RetryStrategy::Complete,
+            execution_mode: RobotExecutionMode::Robot,
+            retry_policy: RetryPolicyConfig {
+                retry_test_tags: vec!["flaky".into()],
+                ..RetryPolicyConfig::default()
+            },
+            total_budget: None,
+            termination_ladder: TerminationLadder::default(),
+        };
+        let output_directory = Utf8PathBuf::from("/tmp/calculator_plan/run");
+        assert!(
+            !robot
+                .command_spec(&output_directory, &output_directory.join("2.xml"), 2, true)
+                .arguments
+                .contains(&"--include".to_string())
+        );
+    }
+
+    #[test]
+    fn create_dry_run_command_spec() {
+        let robot = Robot {
+            robot_target: "~/calculator_test/calculator.robot".into(),
+            n_attempts_max: 2,
+            command_line_args: vec!["--suite".into(), "suite1".into()],
+            envs_rendered_obfuscated: vec![("NAME".into(), "value".into())],
+            retry_strategy: RetryStrategy::Incremental,
+            execution_mode: RobotExecutionMode::Robot,
+            retry_policy: RetryPolicyConfig::default(),
+            total_budget: None,
+            termination_ladder: TerminationLadder::default(),
+        };
+        let output_directory = Utf8PathBuf::from("/tmp/calculator_plan/dry_run");
+        let mut expected = CommandSpec::new(PYTHON_EXECUTABLE);
+        expected
+            .add_argument("-m")
+            .add_argument("robot")
+            .add_argument("--dryrun")
+            .add_argument("--suite")
+            .add_argument("suite1")
+            .add_argument("--outputdir")
+            .add_argument(&output_directory)
+            .add_argument("--output")
+            .add_argument("NONE")
+            .add_argument("--log")
+            .add_argument("NONE")
+            .add_argument("--report")
+            .add_argument("NONE")
+            .add_argument("--consolecolors")
+            .add_argument("off")
+            .add_argument("~/calculator_test/calculator.robot")
+            .add_obfuscated_env("NAME", "value");
+        assert_eq!(robot.dry_run_command_spec(&output_directory), expected);
+    }
+
     #[test]
     fn create_command_obfuscated_env_vars() {
         assert_eq!(
@@ -382,11 +628,16 @@ mod tests {
                 command_line_args: vec![],
                 envs_rendered_obfuscated: vec![("NAME".into(), "value".into())],
                 retry_strategy: RetryStrategy::Complete,
+                execution_mode: RobotExecutionMode::Robot,
+                retry_policy: RetryPolicyConfig::default(),
+                total_budget: None,
+                termination_ladder: TerminationLadder::default(),
             }
             .command_spec(
                 &Utf8PathBuf::default(),
                 &Utf8PathBuf::default().join("out.xml"),
-                1
+                1,
+                false
             )
             .envs_rendered_obfuscated,
             vec![("NAME".into(), "value".into())]
@@ -402,6 +653,10 @@ mod tests {
             command_line_args: vec![],
             envs_rendered_obfuscated: vec![],
             retry_strategy: RetryStrategy::Incremental,
+            execution_mode: RobotExecutionMode::Robot,
+            retry_policy: RetryPolicyConfig::default(),
+            total_budget: None,
+            termination_ladder: TerminationLadder::default(),
         };
         let output_directory =
             Utf8PathBuf::from("/tmp/outputdir/plan_1/2023-08-29T12.23.44.419347+00.00");
@@ -0,0 +1,190 @@
+use crate::history::{RUN_OUTCOME_FILE_NAME, RunOutcome, run_outcome_file};
+
+use anyhow::{Context, Result as AnyhowResult, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::{File, remove_dir_all, remove_file, rename};
+use std::io::Read;
+use std::time::SystemTime;
+use tar::{Archive, Builder};
+use walkdir::WalkDir;
+
+const ARCHIVE_EXTENSION: &str = ".tar.gz";
+
+#[derive(Debug, PartialEq)]
+pub struct ArchivedRun {
+    pub name: String,
+    pub path: Utf8PathBuf,
+    pub size: u64,
+    pub outcome: Option<RunOutcome>,
+}
+
+pub fn is_run_archive(path: &Utf8Path) -> bool {
+    path.as_str().ends_with(ARCHIVE_EXTENSION)
+}
+
+pub fn run_archive_path(run_directory: &Utf8Path) -> Utf8PathBuf {
+    Utf8PathBuf::from(format!("{run_directory}{ARCHIVE_EXTENSION}"))
+}
+
+// Replaces a run directory by a compressed archive. The outcome marker is stored first, such that
+// it can be read without decompressing the entire archive. The archive inherits the modification
+// time of the directory, such that age-based cleanup treats it like the original run.
+pub fn archive_run(run_directory: &Utf8Path) -> AnyhowResult<Utf8PathBuf> {
+    let run_name = run_directory
+        .file_name()
+        .context(format!("{run_directory} has no file name"))?;
+    let modified = run_directory
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .context(format!(
+            "Failed to retrieve modification time of {run_directory}"
+        ))?;
+    let archive_path = run_archive_path(run_directory);
+    let temporary_path = Utf8PathBuf::from(format!("{archive_path}.tmp"));
+    // A leftover temporary file would be mistaken for a run by the next cleanup
+    if let Err(error) = write_archive(run_directory, run_name, &temporary_path, modified) {
+        let _ = remove_file(&temporary_path);
+        return Err(error);
+    }
+    rename(&temporary_path, &archive_path).context(format!(
+        "Failed to rename {temporary_path} to {archive_path}"
+    ))?;
+    remove_dir_all(run_directory).context(format!("Failed to remove {run_directory}"))?;
+    Ok(archive_path)
+}
+
+fn write_archive(
+    run_directory: &Utf8Path,
+    run_name: &str,
+    archive_path: &Utf8Path,
+    modified: SystemTime,
+) -> AnyhowResult<()> {
+    let mut builder = Builder::new(GzEncoder::new(
+        File::create(archive_path).context(format!("Failed to create {archive_path}"))?,
+        Compression::default(),
+    ));
+    let outcome_file = run_outcome_file(run_directory);
+    if outcome_file.exists() {
+        builder.append_path_with_name(&outcome_file, run_outcome_file(Utf8Path::new(run_name)))?;
+    }
+    for entry in WalkDir::new(run_directory).min_depth(1) {
+        let entry = entry?;
+        let path = Utf8Path::from_path(entry.path()).context("Non-UTF-8 path")?;
+        if path == outcome_file {
+            continue;
+        }
+        let name = Utf8Path::new(run_name).join(path.strip_prefix(run_directory)?);
+        if entry.file_type().is_dir() {
+            builder.append_dir(&name, path)?;
+        } else {
+            builder.append_path_with_name(path, &name)?;
+        }
+    }
+    let archive_file = builder
+        .into_inner()?
+        .finish()
+        .context(format!("Failed to write {archive_path}"))?;
+    archive_file
+        .set_modified(modified)
+        .context(format!("Failed to set modification time of {archive_path}"))
+}
+
+pub fn read_archived_run_outcome(archive_path: &Utf8Path) -> AnyhowResult<Option<RunOutcome>> {
+    let mut archive = open(archive_path)?;
+    let Some(entry) = archive.entries()?.next() else {
+        return Ok(None);
+    };
+    let mut entry = entry?;
+    if entry.path()?.file_name() != Some(RUN_OUTCOME_FILE_NAME.as_ref()) {
+        return Ok(None);
+    }
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+    Ok(Some(serde_json::from_str(&content).context(format!(
+        "Failed to parse outcome in {archive_path}"
+    ))?))
+}
+
+// Sorted from oldest to newest, since run directories are named after their start time
+pub fn archived_runs(plan_working_directory: &Utf8Path) -> AnyhowResult<Vec<ArchivedRun>> {
+    let mut archived_runs = vec![];
+    for entry in plan_working_directory
+        .read_dir_utf8()
+        .context(format!("Failed to read {plan_working_directory}"))?
+    {
+        let entry = entry?;
+        let Some(name) = entry.file_name().strip_suffix(ARCHIVE_EXTENSION) else {
+            continue;
+        };
+        archived_runs.push(ArchivedRun {
+            name: name.to_string(),
+            path: entry.path().to_path_buf(),
+            size: entry.metadata()?.len(),
+            // A corrupt archive should not prevent listing the others
+            outcome: read_archived_run_outcome(entry.path()).ok().flatten(),
+        });
+    }
+    archived_runs.sort_by(|left, right| left.name.cmp(&right.name));
+    Ok(archived_runs)
+}
+
+// The run is extracted into a directory named after the run below the target directory.
+pub fn extract_run(archive_path: &Utf8Path, target_directory: &Utf8Path) -> AnyhowResult<()> {
+    if !archive_path.exists() {
+        bail!("{archive_path} does not exist");
+    }
+    open(archive_path)?
+        .unpack(target_directory)
+        .context(format!(
+            "Failed to extract {archive_path} into {target_directory}"
+        ))
+}
+
+fn open(archive_path: &Utf8Path) -> AnyhowResult<Archive<GzDecoder<File>>> {
+    Ok(Archive::new(GzDecoder::new(
+        File::open(archive_path).context(format!("Failed to open {archive_path}"))?,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::write_run_outcome;
+    use std::fs::{create_dir_all, read_to_string, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn archive_and_extract_run() -> AnyhowResult<()> {
+        let temp_dir = tempdir()?;
+        let plan_working_directory = Utf8Path::from_path(temp_dir.path()).unwrap();
+        let run_directory = plan_working_directory.join("2024-01-01T00.00.00.000000000+0000");
+        create_dir_all(run_directory.join("1"))?;
+        write(run_directory.join("1").join("output.xml"), "<robot/>")?;
+        write_run_outcome(&run_directory, RunOutcome::TestFailures)?;
+
+        let archive_path = archive_run(&run_directory)?;
+
+        assert!(!run_directory.exists());
+        assert_eq!(
+            archived_runs(plan_working_directory)?,
+            [ArchivedRun {
+                name: "2024-01-01T00.00.00.000000000+0000".into(),
+                path: archive_path.clone(),
+                size: archive_path.metadata()?.len(),
+                outcome: Some(RunOutcome::TestFailures),
+            }]
+        );
+        let target_directory = plan_working_directory.join("extracted");
+        extract_run(&archive_path, &target_directory)?;
+        let extracted_run = target_directory.join("2024-01-01T00.00.00.000000000+0000");
+        assert_eq!(
+            read_to_string(extracted_run.join("1").join("output.xml"))?,
+            "<robot/>"
+        );
+        assert!(run_outcome_file(&extracted_run).exists());
+        Ok(())
+    }
+}
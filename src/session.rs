@@ -1,6 +1,6 @@
-use crate::child_process_supervisor::{ChildProcessSupervisor, StdioPaths};
+use crate::child_process_supervisor::{ChildProcessSupervisor, ProcessReport, StdioPaths};
 use crate::command_spec::CommandSpec;
-use crate::config::SessionConfig;
+use crate::config::{SessionConfig, TerminationLadder};
 use crate::tasks::{TaskSpec, run_task};
 use crate::termination::Outcome;
 
@@ -27,9 +27,14 @@ impl Session {
     }
 
     pub fn run(&self, spec: &RunSpec) -> AnyhowResult<Outcome<i32>> {
+        Ok(self.run_with_report(spec)?.0)
+    }
+
+    // The process report is empty for user sessions, since the task scheduler runs the process.
+    pub fn run_with_report(&self, spec: &RunSpec) -> AnyhowResult<(Outcome<i32>, ProcessReport)> {
         match self {
             Self::Current(current_session) => current_session.run(spec),
-            Self::User(user_session) => user_session.run(spec),
+            Self::User(user_session) => Ok((user_session.run(spec)?, ProcessReport::default())),
         }
     }
 
@@ -80,11 +85,12 @@ pub struct RunSpec<'a> {
     pub runtime_base_path: &'a Utf8Path,
     pub timeout: u64,
     pub cancellation_token: &'a CancellationToken,
+    pub termination_ladder: &'a TerminationLadder,
 }
 
 impl CurrentSession {
-    fn run(&self, spec: &RunSpec) -> AnyhowResult<Outcome<i32>> {
-        match (ChildProcessSupervisor {
+    fn run(&self, spec: &RunSpec) -> AnyhowResult<(Outcome<i32>, ProcessReport)> {
+        let (outcome, process_report) = ChildProcessSupervisor {
             command_spec: spec.command_spec,
             stdio_paths: Some(StdioPaths {
                 stdout: Utf8PathBuf::from(format!("{}.stdout", spec.runtime_base_path)),
@@ -92,17 +98,19 @@ impl CurrentSession {
             }),
             timeout: spec.timeout,
             cancellation_token: spec.cancellation_token,
+            termination_ladder: spec.termination_ladder,
         }
-        .run())?
-        {
-            Outcome::Completed(exit_status) => Ok(Outcome::Completed(
+        .run()?;
+        let outcome = match outcome {
+            Outcome::Completed(exit_status) => Outcome::Completed(
                 exit_status
                     .code()
                     .context("Failed to retrieve exit code of subprocess")?,
-            )),
-            Outcome::Timeout => Ok(Outcome::Timeout),
-            Outcome::Cancel => Ok(Outcome::Cancel),
-        }
+            ),
+            Outcome::Timeout => Outcome::Timeout,
+            Outcome::Cancel => Outcome::Cancel,
+        };
+        Ok((outcome, process_report))
     }
 
     pub fn id(&self) -> String {
@@ -251,11 +251,16 @@ fn build_task_script(task_name: &str, command_spec: &CommandSpec, paths: &Paths)
         .map(|(k, v)| format!("set \"{k}={v}\""))
         .collect::<Vec<_>>()
         .join("\n");
-    [
+    let mut lines = vec![
         String::from("@echo off"),
         String::from("setlocal"),
         format!("echo Robotmk: running task {task_name}. Please do not close this window."),
         set_envs,
+    ];
+    if let Some(current_directory) = &command_spec.current_directory {
+        lines.push(format!("cd /d \"{current_directory}\""));
+    }
+    lines.extend([
         format!(
             "{} > {} 2> {}",
             command_spec.to_command_string(),
@@ -264,8 +269,8 @@ fn build_task_script(task_name: &str, command_spec: &CommandSpec, paths: &Paths)
         ),
         format!("echo %errorlevel% > {}", paths.exit_code),
         String::from("endlocal"),
-    ]
-    .join("\n")
+    ]);
+    lines.join("\n")
 }
 
 fn read_exit_code(path: &Utf8Path) -> AnyhowResult<i32> {
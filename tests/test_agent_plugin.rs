@@ -1,6 +1,8 @@
 use assert_cmd::cargo::cargo_bin_cmd;
 use camino::{Utf8Path, Utf8PathBuf};
-use robotmk::config::{CondaConfig, Config, RCCConfig, RCCProfileConfig};
+use robotmk::config::{
+    CleanupConfig, CondaConfig, Config, HistoryConfig, RCCConfig, RCCProfileConfig,
+};
 use robotmk::lock::Locker;
 use robotmk::results::{ConfigSection, results_directory};
 use robotmk::section::{Host, WritePiggybackSection, WriteSection};
@@ -36,6 +38,8 @@ fn test_agent_plugin() -> anyhow::Result<()> {
 <<<piggyback_section:sep(0)>>>
 {\"x\":true,\"y\":\"some-string\"}
 <<<<>>>>
+<<<robotmk_stale_results:sep(0)>>>
+{\"scheduler_heartbeat_age\":null,\"scheduler_heartbeat_stale\":true,\"plans\":[]}
 "
         )
     );
@@ -55,6 +59,8 @@ fn create_config(runtime_dir: &Utf8Path) -> Config {
             micromamba_binary_path: "/micromamba".into(),
             base_directory: Utf8PathBuf::default(),
         },
+        history_config: HistoryConfig::default(),
+        cleanup_config: CleanupConfig::default(),
         plan_groups: vec![],
     }
 }
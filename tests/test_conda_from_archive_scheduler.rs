@@ -5,10 +5,12 @@ use anyhow::{Result as AnyhowResult, bail};
 use assert_cmd::cargo_bin;
 use camino::{Utf8Path, Utf8PathBuf};
 use robotmk::config::{
-    CondaConfig, CondaEnvironmentConfig, CondaEnvironmentSource, Config, EnvironmentConfig,
-    ExecutionConfig, HTTPProxyConfig, PlanConfig, PlanMetadata, RCCConfig, RCCProfileConfig,
-    RetryStrategy, RobotConfig, SequentialPlanGroup, SessionConfig, Source,
-    TlsCertificateValidation, WorkingDirectoryCleanupConfig,
+    CleanupConfig, CondaConfig, CondaEnvironmentConfig, CondaEnvironmentSource, Config,
+    DryRunValidationConfig, EnvironmentConfig, ExecutionConfig, GroupExecutionMode,
+    HTTPProxyConfig, HistoryConfig, HooksConfig, MissedTickPolicy, PlanConfig, PlanMetadata,
+    RCCConfig, RCCProfileConfig, RetryPolicyConfig, RetryStrategy, RobotConfig, RobotExecutionMode,
+    SequentialPlanGroup, SessionConfig, Source, TerminationLadder, TlsCertificateValidation,
+    WorkingDirectoryCleanupConfig,
 };
 use robotmk::results::results_directory;
 use robotmk::section::Host;
@@ -99,6 +101,8 @@ fn create_config(
             robocorp_home_base: Utf8PathBuf::default(),
         },
         conda_config,
+        history_config: HistoryConfig::default(),
+        cleanup_config: CleanupConfig::default(),
         plan_groups: vec![SequentialPlanGroup {
             plans: vec![PlanConfig {
                 id: plan_id.into(),
@@ -117,11 +121,15 @@ fn create_config(
                     argument_files: vec![],
                     exit_on_failure: false,
                     environment_variables_rendered_obfuscated: vec![],
+                    execution_mode: RobotExecutionMode::Robot,
                 },
                 execution_config: ExecutionConfig {
                     n_attempts_max: 1,
                     retry_strategy: RetryStrategy::Complete,
                     timeout: 10,
+                    retry_policy: RetryPolicyConfig::default(),
+                    total_budget: None,
+                    termination_ladder: TerminationLadder::default(),
                 },
                 environment_config: EnvironmentConfig::Conda(CondaEnvironmentConfig {
                     source: CondaEnvironmentSource::Archive(packed_conda_env_path.into()),
@@ -133,6 +141,10 @@ fn create_config(
                 }),
                 session_config: SessionConfig::Current,
                 working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxExecutions(4),
+                hooks: HooksConfig::default(),
+                dry_run_validation: DryRunValidationConfig::Disabled,
+                dependencies: vec![],
+                failed_run_cleanup_config: None,
                 host: Host::Source,
                 metadata: PlanMetadata {
                     application: "app".into(),
@@ -141,6 +153,8 @@ fn create_config(
                 },
             }],
             execution_interval: 30,
+            missed_tick_policy: MissedTickPolicy::Burst,
+            execution_mode: GroupExecutionMode::Sequential,
         }],
     }
 }
@@ -219,8 +233,10 @@ fn assert_results_directory(results_directory: &Utf8Path, plan_id: &str) {
         directory_entries(results_directory, 2),
         [
             "environment_build_states.json",
+            "live_status.json",
             "plans",
             &format!("plans/{plan_id}.json"),
+            "scheduler_heartbeat.json",
             "scheduler_phase.json",
             "setup_failures.json"
         ]
@@ -2,7 +2,9 @@ pub mod helper;
 use crate::helper::var;
 use camino::Utf8PathBuf;
 use chrono::Utc;
-use robotmk::config::{CondaEnvironmentSource, HTTPProxyConfig, TlsCertificateValidation};
+use robotmk::config::{
+    CondaEnvironmentSource, HTTPProxyConfig, TerminationLadder, TlsCertificateValidation,
+};
 use robotmk::env::{Environment, conda::CondaEnvironment};
 use robotmk::results::BuildOutcome;
 use robotmk::session::{CurrentSession, Session};
@@ -37,6 +39,7 @@ fn test_conda_post_build_commands() -> anyhow::Result<()> {
         tls_certificate_validation: TlsCertificateValidation::Enabled,
         tls_revokation_enabled: false,
         build_timeout: var("BUILD_TIMEOUT")?.parse::<u64>()?,
+        termination_ladder: TerminationLadder::default(),
         build_runtime_directory: temp_dir_path,
     })
     .build(
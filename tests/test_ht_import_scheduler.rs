@@ -5,9 +5,11 @@ use anyhow::{Result as AnyhowResult, bail};
 use assert_cmd::cargo_bin;
 use camino::{Utf8Path, Utf8PathBuf};
 use robotmk::config::{
-    CondaConfig, Config, EnvironmentConfig, ExecutionConfig, PlanConfig, PlanMetadata, RCCConfig,
-    RCCEnvironmentConfig, RCCProfileConfig, RetryStrategy, RobotConfig, SequentialPlanGroup,
-    SessionConfig, Source, WorkingDirectoryCleanupConfig,
+    CleanupConfig, CondaConfig, Config, DryRunValidationConfig, EnvironmentConfig, ExecutionConfig,
+    GroupExecutionMode, HistoryConfig, HooksConfig, MissedTickPolicy, PlanConfig, PlanMetadata,
+    RCCConfig, RCCEnvironmentConfig, RCCProfileConfig, RetryPolicyConfig, RetryStrategy,
+    RobotConfig, RobotExecutionMode, SequentialPlanGroup, SessionConfig, Source, TerminationLadder,
+    WorkingDirectoryCleanupConfig,
 };
 use robotmk::results::results_directory;
 use robotmk::section::Host;
@@ -90,6 +92,8 @@ fn create_config(
         runtime_directory: test_dir.into(),
         rcc_config,
         conda_config,
+        history_config: HistoryConfig::default(),
+        cleanup_config: CleanupConfig::default(),
         plan_groups: vec![SequentialPlanGroup {
             plans: vec![PlanConfig {
                 id: "rcc_headless".into(),
@@ -108,11 +112,15 @@ fn create_config(
                     argument_files: vec![],
                     exit_on_failure: false,
                     environment_variables_rendered_obfuscated: vec![],
+                    execution_mode: RobotExecutionMode::Robot,
                 },
                 execution_config: ExecutionConfig {
                     n_attempts_max: 1,
                     retry_strategy: RetryStrategy::Complete,
                     timeout: 10,
+                    retry_policy: RetryPolicyConfig::default(),
+                    total_budget: None,
+                    termination_ladder: TerminationLadder::default(),
                 },
                 environment_config: EnvironmentConfig::Rcc(RCCEnvironmentConfig {
                     robot_yaml_path: "robot.yaml".into(),
@@ -122,6 +130,10 @@ fn create_config(
                 }),
                 session_config: SessionConfig::Current,
                 working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxExecutions(4),
+                hooks: HooksConfig::default(),
+                dry_run_validation: DryRunValidationConfig::Disabled,
+                dependencies: vec![],
+                failed_run_cleanup_config: None,
                 host: Host::Source,
                 metadata: PlanMetadata {
                     application: "app".into(),
@@ -130,6 +142,8 @@ fn create_config(
                 },
             }],
             execution_interval: 30,
+            missed_tick_policy: MissedTickPolicy::Burst,
+            execution_mode: GroupExecutionMode::Sequential,
         }],
     }
 }
@@ -210,8 +224,10 @@ fn assert_results_directory(results_directory: &Utf8Path) {
         directory_entries(results_directory, 2),
         [
             "environment_build_states.json",
+            "live_status.json",
             "plans",
             "plans/rcc_headless.json",
+            "scheduler_heartbeat.json",
             "scheduler_phase.json",
             "setup_failures.json"
         ]
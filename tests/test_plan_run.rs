@@ -1,8 +1,8 @@
 use anyhow::Result as AnyhowResult;
 use camino::Utf8Path;
-use robotmk::config::RetryStrategy;
+use robotmk::config::{RetryPolicyConfig, RetryStrategy, RobotExecutionMode, TerminationLadder};
 use robotmk::env::{Environment, system::SystemEnvironment};
-use robotmk::plans::run_attempts_with_rebot;
+use robotmk::plans::{PlanRunSpec, run_attempts_with_rebot};
 use robotmk::results::AttemptOutcome;
 use robotmk::rf::robot::Robot;
 use robotmk::session::{CurrentSession, Session};
@@ -20,16 +20,21 @@ fn test_rebot_run() -> AnyhowResult<()> {
         command_line_args: vec![],
         envs_rendered_obfuscated: vec![],
         retry_strategy: RetryStrategy::Complete,
+        execution_mode: RobotExecutionMode::Robot,
+        retry_policy: RetryPolicyConfig::default(),
+        total_budget: None,
+        termination_ladder: TerminationLadder::default(),
     };
-    let (attempt_reports, rebot) = run_attempts_with_rebot(
-        &robot,
-        "test",
-        &Environment::System(SystemEnvironment {}),
-        &Session::Current(CurrentSession {}),
-        10,
-        &CancellationToken::default(),
-        test_dir_path,
-    )?;
+    let (attempt_reports, rebot) = run_attempts_with_rebot(&PlanRunSpec {
+        id: "test",
+        robot: &robot,
+        environment: &Environment::System(SystemEnvironment {}),
+        session: &Session::Current(CurrentSession {}),
+        timeout: 10,
+        cancellation_token: &CancellationToken::default(),
+        output_directory: test_dir_path,
+        report_activity: &|_| {},
+    })?;
     assert_eq!(attempt_reports.len(), 1);
     let attempt_report = &attempt_reports[0];
     assert_eq!(attempt_report.index, 1);
@@ -50,16 +55,21 @@ fn test_timeout_process() -> AnyhowResult<()> {
         command_line_args: vec!["--variable".into(), format!("RESOURCE:{resource}")],
         envs_rendered_obfuscated: vec![],
         retry_strategy: RetryStrategy::Complete,
+        execution_mode: RobotExecutionMode::Robot,
+        retry_policy: RetryPolicyConfig::default(),
+        total_budget: None,
+        termination_ladder: TerminationLadder::default(),
     };
-    let (attempt_reports, rebot) = run_attempts_with_rebot(
-        &robot,
-        "test",
-        &Environment::System(SystemEnvironment {}),
-        &Session::Current(CurrentSession {}),
-        1,
-        &CancellationToken::default(),
-        test_dir_path,
-    )?;
+    let (attempt_reports, rebot) = run_attempts_with_rebot(&PlanRunSpec {
+        id: "test",
+        robot: &robot,
+        environment: &Environment::System(SystemEnvironment {}),
+        session: &Session::Current(CurrentSession {}),
+        timeout: 1,
+        cancellation_token: &CancellationToken::default(),
+        output_directory: test_dir_path,
+        report_activity: &|_| {},
+    })?;
     assert_eq!(attempt_reports.len(), 1);
     let attempt_report = &attempt_reports[0];
     assert_eq!(attempt_report.index, 1);
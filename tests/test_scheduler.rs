@@ -7,10 +7,12 @@ use camino::{Utf8Path, Utf8PathBuf};
 #[cfg(windows)]
 use robotmk::config::UserSessionConfig;
 use robotmk::config::{
-    CondaConfig, CondaEnvironmentConfig, CondaEnvironmentSource, Config, CustomRCCProfileConfig,
-    EnvironmentConfig, ExecutionConfig, HTTPProxyConfig, PlanConfig, PlanMetadata, RCCConfig,
-    RCCEnvironmentConfig, RCCProfileConfig, RetryStrategy, RobotConfig, SequentialPlanGroup,
-    SessionConfig, Source, TlsCertificateValidation, WorkingDirectoryCleanupConfig,
+    CleanupConfig, CondaConfig, CondaEnvironmentConfig, CondaEnvironmentSource, Config,
+    CustomRCCProfileConfig, DryRunValidationConfig, EnvironmentConfig, ExecutionConfig,
+    GroupExecutionMode, HTTPProxyConfig, HistoryConfig, HooksConfig, MissedTickPolicy, PlanConfig,
+    PlanMetadata, RCCConfig, RCCEnvironmentConfig, RCCProfileConfig, RetryPolicyConfig,
+    RetryStrategy, RobotConfig, RobotExecutionMode, SequentialPlanGroup, SessionConfig, Source,
+    TerminationLadder, TlsCertificateValidation, WorkingDirectoryCleanupConfig,
 };
 use robotmk::results::results_directory;
 use robotmk::section::Host;
@@ -172,6 +174,8 @@ fn create_config(
         runtime_directory: runtime_dir.into(),
         rcc_config,
         conda_config,
+        history_config: HistoryConfig::default(),
+        cleanup_config: CleanupConfig::default(),
         plan_groups: vec![
             SequentialPlanGroup {
                 plans: vec![
@@ -192,11 +196,15 @@ fn create_config(
                             argument_files: vec![],
                             exit_on_failure: false,
                             environment_variables_rendered_obfuscated: vec![],
+                            execution_mode: RobotExecutionMode::Robot,
                         },
                         execution_config: ExecutionConfig {
                             n_attempts_max: 1,
                             retry_strategy: RetryStrategy::Complete,
                             timeout: 10,
+                            retry_policy: RetryPolicyConfig::default(),
+                            total_budget: None,
+                            termination_ladder: TerminationLadder::default(),
                         },
                         environment_config: EnvironmentConfig::Rcc(RCCEnvironmentConfig {
                             robot_yaml_path: "robot.yaml".into(),
@@ -207,6 +215,10 @@ fn create_config(
                         session_config: SessionConfig::Current,
                         working_directory_cleanup_config:
                             WorkingDirectoryCleanupConfig::MaxExecutions(4),
+                        hooks: HooksConfig::default(),
+                        dry_run_validation: DryRunValidationConfig::Disabled,
+                        dependencies: vec![],
+                        failed_run_cleanup_config: None,
                         host: Host::Source,
                         metadata: PlanMetadata {
                             application: "app".into(),
@@ -232,11 +244,15 @@ fn create_config(
                             argument_files: vec![],
                             exit_on_failure: false,
                             environment_variables_rendered_obfuscated: vec![],
+                            execution_mode: RobotExecutionMode::Robot,
                         },
                         execution_config: ExecutionConfig {
                             n_attempts_max: 1,
                             retry_strategy: RetryStrategy::Complete,
                             timeout: 15,
+                            retry_policy: RetryPolicyConfig::default(),
+                            total_budget: None,
+                            termination_ladder: TerminationLadder::default(),
                         },
                         environment_config: EnvironmentConfig::Rcc(RCCEnvironmentConfig {
                             robot_yaml_path: "robot.yaml".into(),
@@ -250,6 +266,10 @@ fn create_config(
                         working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxAgeSecs(
                             120,
                         ),
+                        hooks: HooksConfig::default(),
+                        dry_run_validation: DryRunValidationConfig::Disabled,
+                        dependencies: vec![],
+                        failed_run_cleanup_config: None,
                         host: Host::Source,
                         metadata: PlanMetadata {
                             application: "app".into(),
@@ -276,11 +296,15 @@ fn create_config(
                             argument_files: vec![],
                             exit_on_failure: false,
                             environment_variables_rendered_obfuscated: vec![],
+                            execution_mode: RobotExecutionMode::Robot,
                         },
                         execution_config: ExecutionConfig {
                             n_attempts_max: 1,
                             retry_strategy: RetryStrategy::Complete,
                             timeout: 15,
+                            retry_policy: RetryPolicyConfig::default(),
+                            total_budget: None,
+                            termination_ladder: TerminationLadder::default(),
                         },
                         environment_config: EnvironmentConfig::Rcc(RCCEnvironmentConfig {
                             robot_yaml_path: "robot.yaml".into(),
@@ -297,6 +321,10 @@ fn create_config(
                         working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxAgeSecs(
                             120,
                         ),
+                        hooks: HooksConfig::default(),
+                        dry_run_validation: DryRunValidationConfig::Disabled,
+                        dependencies: vec![],
+                        failed_run_cleanup_config: None,
                         host: Host::Source,
                         metadata: PlanMetadata {
                             application: "managed".into(),
@@ -306,6 +334,8 @@ fn create_config(
                     },
                 ],
                 execution_interval: 30,
+                missed_tick_policy: MissedTickPolicy::Burst,
+                execution_mode: GroupExecutionMode::Sequential,
             },
             SequentialPlanGroup {
                 plans: vec![
@@ -326,11 +356,15 @@ fn create_config(
                             argument_files: vec![],
                             exit_on_failure: false,
                             environment_variables_rendered_obfuscated: vec![],
+                            execution_mode: RobotExecutionMode::Robot,
                         },
                         execution_config: ExecutionConfig {
                             n_attempts_max: 1,
                             retry_strategy: RetryStrategy::Complete,
                             timeout: 10,
+                            retry_policy: RetryPolicyConfig::default(),
+                            total_budget: None,
+                            termination_ladder: TerminationLadder::default(),
                         },
                         environment_config: EnvironmentConfig::Conda(CondaEnvironmentConfig {
                             source: CondaEnvironmentSource::Manifest("conda.yaml".into()),
@@ -343,6 +377,10 @@ fn create_config(
                         session_config: SessionConfig::Current,
                         working_directory_cleanup_config:
                             WorkingDirectoryCleanupConfig::MaxExecutions(4),
+                        hooks: HooksConfig::default(),
+                        dry_run_validation: DryRunValidationConfig::Disabled,
+                        dependencies: vec![],
+                        failed_run_cleanup_config: None,
                         host: Host::Source,
                         metadata: PlanMetadata {
                             application: "app".into(),
@@ -368,11 +406,15 @@ fn create_config(
                             argument_files: vec![],
                             exit_on_failure: false,
                             environment_variables_rendered_obfuscated: vec![],
+                            execution_mode: RobotExecutionMode::Robot,
                         },
                         execution_config: ExecutionConfig {
                             n_attempts_max: 1,
                             retry_strategy: RetryStrategy::Complete,
                             timeout: 15,
+                            retry_policy: RetryPolicyConfig::default(),
+                            total_budget: None,
+                            termination_ladder: TerminationLadder::default(),
                         },
                         environment_config: EnvironmentConfig::Conda(CondaEnvironmentConfig {
                             source: CondaEnvironmentSource::Manifest("conda.yaml".into()),
@@ -388,6 +430,10 @@ fn create_config(
                         working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxAgeSecs(
                             120,
                         ),
+                        hooks: HooksConfig::default(),
+                        dry_run_validation: DryRunValidationConfig::Disabled,
+                        dependencies: vec![],
+                        failed_run_cleanup_config: None,
                         host: Host::Source,
                         metadata: PlanMetadata {
                             application: "app".into(),
@@ -414,11 +460,15 @@ fn create_config(
                             argument_files: vec![],
                             exit_on_failure: false,
                             environment_variables_rendered_obfuscated: vec![],
+                            execution_mode: RobotExecutionMode::Robot,
                         },
                         execution_config: ExecutionConfig {
                             n_attempts_max: 1,
                             retry_strategy: RetryStrategy::Complete,
                             timeout: 15,
+                            retry_policy: RetryPolicyConfig::default(),
+                            total_budget: None,
+                            termination_ladder: TerminationLadder::default(),
                         },
                         environment_config: EnvironmentConfig::Conda(CondaEnvironmentConfig {
                             source: CondaEnvironmentSource::Manifest("conda.yaml".into()),
@@ -437,6 +487,10 @@ fn create_config(
                         working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxAgeSecs(
                             120,
                         ),
+                        hooks: HooksConfig::default(),
+                        dry_run_validation: DryRunValidationConfig::Disabled,
+                        dependencies: vec![],
+                        failed_run_cleanup_config: None,
                         host: Host::Source,
                         metadata: PlanMetadata {
                             application: "managed".into(),
@@ -446,6 +500,8 @@ fn create_config(
                     },
                 ],
                 execution_interval: 30,
+                missed_tick_policy: MissedTickPolicy::Burst,
+                execution_mode: GroupExecutionMode::Sequential,
             },
             // Note: For our test, it doesn't matter if the suite can be executed on the target
             // system. We are not checking for success. So even on systems with no Python, the test
@@ -468,17 +524,25 @@ fn create_config(
                         argument_files: vec![],
                         exit_on_failure: false,
                         environment_variables_rendered_obfuscated: vec![],
+                        execution_mode: RobotExecutionMode::Robot,
                     },
                     execution_config: ExecutionConfig {
                         n_attempts_max: 1,
                         retry_strategy: RetryStrategy::Complete,
                         timeout: 17,
+                        retry_policy: RetryPolicyConfig::default(),
+                        total_budget: None,
+                        termination_ladder: TerminationLadder::default(),
                     },
                     environment_config: EnvironmentConfig::System,
                     session_config: SessionConfig::Current,
                     working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxExecutions(
                         4,
                     ),
+                    hooks: HooksConfig::default(),
+                    dry_run_validation: DryRunValidationConfig::Disabled,
+                    dependencies: vec![],
+                    failed_run_cleanup_config: None,
                     host: Host::Piggyback("oink".into()),
                     metadata: PlanMetadata {
                         application: "app3".into(),
@@ -487,6 +551,8 @@ fn create_config(
                     },
                 }],
                 execution_interval: 37,
+                missed_tick_policy: MissedTickPolicy::Burst,
+                execution_mode: GroupExecutionMode::Sequential,
             },
         ],
     }
@@ -705,6 +771,7 @@ fn assert_results_directory(results_directory: &Utf8Path) {
         directory_entries(results_directory, 2),
         [
             "environment_build_states.json",
+            "live_status.json",
             "plans",
             #[cfg(windows)]
             "plans/conda_headed.json",
@@ -715,6 +782,7 @@ fn assert_results_directory(results_directory: &Utf8Path) {
             "plans/rcc_headless.json",
             "plans/rcc_managed_robot.json",
             "plans/system_env.json",
+            "scheduler_heartbeat.json",
             "scheduler_phase.json",
             "setup_failures.json"
         ]
@@ -728,7 +796,12 @@ async fn assert_managed_directory(
     assert!(managed_directory.is_dir());
     assert_eq!(
         directory_entries(managed_directory, 1),
-        ["conda_managed_robot", "rcc_managed_robot"]
+        [
+            "conda_managed_robot",
+            "conda_managed_robot.version.json",
+            "rcc_managed_robot",
+            "rcc_managed_robot.version.json"
+        ]
     );
     #[cfg(windows)]
     {
@@ -4,9 +4,11 @@ use crate::helper::{run_scheduler, var};
 use anyhow::Result as AnyhowResult;
 use camino::{Utf8Path, Utf8PathBuf};
 use robotmk::config::{
-    CondaConfig, CondaEnvironmentConfig, CondaEnvironmentSource, Config, EnvironmentConfig,
-    ExecutionConfig, HTTPProxyConfig, PlanConfig, PlanMetadata, RCCConfig, RCCEnvironmentConfig,
-    RCCProfileConfig, RetryStrategy, RobotConfig, SequentialPlanGroup, SessionConfig, Source,
+    CleanupConfig, CondaConfig, CondaEnvironmentConfig, CondaEnvironmentSource, Config,
+    DryRunValidationConfig, EnvironmentConfig, ExecutionConfig, GroupExecutionMode,
+    HTTPProxyConfig, HistoryConfig, HooksConfig, MissedTickPolicy, PlanConfig, PlanMetadata,
+    RCCConfig, RCCEnvironmentConfig, RCCProfileConfig, RetryPolicyConfig, RetryStrategy,
+    RobotConfig, RobotExecutionMode, SequentialPlanGroup, SessionConfig, Source, TerminationLadder,
     TlsCertificateValidation, WorkingDirectoryCleanupConfig,
 };
 use robotmk::section::Host;
@@ -60,6 +62,8 @@ fn create_config(
         runtime_directory: runtime_dir.into(),
         rcc_config,
         conda_config,
+        history_config: HistoryConfig::default(),
+        cleanup_config: CleanupConfig::default(),
         plan_groups: vec![SequentialPlanGroup {
             plans: vec![
                 PlanConfig {
@@ -79,11 +83,15 @@ fn create_config(
                         argument_files: vec![],
                         exit_on_failure: false,
                         environment_variables_rendered_obfuscated: vec![],
+                        execution_mode: RobotExecutionMode::Robot,
                     },
                     execution_config: ExecutionConfig {
                         n_attempts_max: 1,
                         retry_strategy: RetryStrategy::Complete,
                         timeout: 10,
+                        retry_policy: RetryPolicyConfig::default(),
+                        total_budget: None,
+                        termination_ladder: TerminationLadder::default(),
                     },
                     environment_config: EnvironmentConfig::Conda(CondaEnvironmentConfig {
                         source: CondaEnvironmentSource::Manifest("conda.yaml".into()),
@@ -97,6 +105,10 @@ fn create_config(
                     working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxExecutions(
                         4,
                     ),
+                    hooks: HooksConfig::default(),
+                    dry_run_validation: DryRunValidationConfig::Disabled,
+                    dependencies: vec![],
+                    failed_run_cleanup_config: None,
                     host: Host::Source,
                     metadata: PlanMetadata {
                         application: "app".into(),
@@ -121,11 +133,15 @@ fn create_config(
                         argument_files: vec![],
                         exit_on_failure: false,
                         environment_variables_rendered_obfuscated: vec![],
+                        execution_mode: RobotExecutionMode::Robot,
                     },
                     execution_config: ExecutionConfig {
                         n_attempts_max: 1,
                         retry_strategy: RetryStrategy::Complete,
                         timeout: 10,
+                        retry_policy: RetryPolicyConfig::default(),
+                        total_budget: None,
+                        termination_ladder: TerminationLadder::default(),
                     },
                     environment_config: EnvironmentConfig::Rcc(RCCEnvironmentConfig {
                         robot_yaml_path: "robot.yaml".into(),
@@ -137,6 +153,10 @@ fn create_config(
                     working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxExecutions(
                         4,
                     ),
+                    hooks: HooksConfig::default(),
+                    dry_run_validation: DryRunValidationConfig::Disabled,
+                    dependencies: vec![],
+                    failed_run_cleanup_config: None,
                     host: Host::Source,
                     metadata: PlanMetadata {
                         application: "app".into(),
@@ -146,6 +166,8 @@ fn create_config(
                 },
             ],
             execution_interval: 30,
+            missed_tick_policy: MissedTickPolicy::Burst,
+            execution_mode: GroupExecutionMode::Sequential,
         }],
     }
 }
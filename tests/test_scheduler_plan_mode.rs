@@ -5,9 +5,11 @@ use anyhow::{Result as AnyhowResult, bail};
 use assert_cmd::cargo_bin;
 use camino::{Utf8Path, Utf8PathBuf};
 use robotmk::config::{
-    CondaConfig, CondaEnvironmentConfig, CondaEnvironmentSource, Config, CustomRCCProfileConfig,
-    EnvironmentConfig, ExecutionConfig, HTTPProxyConfig, PlanConfig, PlanMetadata, RCCConfig,
-    RCCProfileConfig, RetryStrategy, RobotConfig, SequentialPlanGroup, SessionConfig, Source,
+    CleanupConfig, CondaConfig, CondaEnvironmentConfig, CondaEnvironmentSource, Config,
+    CustomRCCProfileConfig, DryRunValidationConfig, EnvironmentConfig, ExecutionConfig,
+    GroupExecutionMode, HTTPProxyConfig, HistoryConfig, HooksConfig, MissedTickPolicy, PlanConfig,
+    PlanMetadata, RCCConfig, RCCProfileConfig, RetryPolicyConfig, RetryStrategy, RobotConfig,
+    RobotExecutionMode, SequentialPlanGroup, SessionConfig, Source, TerminationLadder,
     TlsCertificateValidation, WorkingDirectoryCleanupConfig,
 };
 use robotmk::results::{plan_results_directory, results_directory};
@@ -164,6 +166,8 @@ fn create_config(
         runtime_directory: runtime_dir.into(),
         rcc_config,
         conda_config,
+        history_config: HistoryConfig::default(),
+        cleanup_config: CleanupConfig::default(),
         plan_groups: vec![SequentialPlanGroup {
             plans: vec![
                 PlanConfig {
@@ -183,11 +187,15 @@ fn create_config(
                         argument_files: vec![],
                         exit_on_failure: false,
                         environment_variables_rendered_obfuscated: vec![],
+                        execution_mode: RobotExecutionMode::Robot,
                     },
                     execution_config: ExecutionConfig {
                         n_attempts_max: 1,
                         retry_strategy: RetryStrategy::Complete,
                         timeout: 10,
+                        retry_policy: RetryPolicyConfig::default(),
+                        total_budget: None,
+                        termination_ladder: TerminationLadder::default(),
                     },
                     environment_config: EnvironmentConfig::Conda(CondaEnvironmentConfig {
                         source: CondaEnvironmentSource::Manifest("conda.yaml".into()),
@@ -201,6 +209,10 @@ fn create_config(
                     working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxExecutions(
                         4,
                     ),
+                    hooks: HooksConfig::default(),
+                    dry_run_validation: DryRunValidationConfig::Disabled,
+                    dependencies: vec![],
+                    failed_run_cleanup_config: None,
                     host: Host::Source,
                     metadata: PlanMetadata {
                         application: "app".into(),
@@ -225,11 +237,15 @@ fn create_config(
                         argument_files: vec![],
                         exit_on_failure: false,
                         environment_variables_rendered_obfuscated: vec![],
+                        execution_mode: RobotExecutionMode::Robot,
                     },
                     execution_config: ExecutionConfig {
                         n_attempts_max: 1,
                         retry_strategy: RetryStrategy::Complete,
                         timeout: 10,
+                        retry_policy: RetryPolicyConfig::default(),
+                        total_budget: None,
+                        termination_ladder: TerminationLadder::default(),
                     },
                     environment_config: EnvironmentConfig::Conda(CondaEnvironmentConfig {
                         source: CondaEnvironmentSource::Manifest("conda.yaml".into()),
@@ -243,6 +259,10 @@ fn create_config(
                     working_directory_cleanup_config: WorkingDirectoryCleanupConfig::MaxExecutions(
                         4,
                     ),
+                    hooks: HooksConfig::default(),
+                    dry_run_validation: DryRunValidationConfig::Disabled,
+                    dependencies: vec![],
+                    failed_run_cleanup_config: None,
                     host: Host::Source,
                     metadata: PlanMetadata {
                         application: "app".into(),
@@ -252,6 +272,8 @@ fn create_config(
                 },
             ],
             execution_interval: 30,
+            missed_tick_policy: MissedTickPolicy::Burst,
+            execution_mode: GroupExecutionMode::Sequential,
         }],
     }
 }